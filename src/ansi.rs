@@ -0,0 +1,255 @@
+//! Parsing ANSI escape codes out of log lines, keeping SGR styling.
+
+/// One of the 16 standard terminal colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    const ALL: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+
+    pub fn to_iced(self) -> iced::Color {
+        let rgb = |r, g, b| iced::Color::from_rgb8(r, g, b);
+        match self {
+            Color::Black => rgb(0x55, 0x55, 0x55),
+            Color::Red => rgb(0xcd, 0x31, 0x31),
+            Color::Green => rgb(0x0d, 0xbc, 0x79),
+            Color::Yellow => rgb(0xe5, 0xe5, 0x10),
+            Color::Blue => rgb(0x24, 0x72, 0xc8),
+            Color::Magenta => rgb(0xbc, 0x3f, 0xbc),
+            Color::Cyan => rgb(0x11, 0xa8, 0xcd),
+            Color::White => rgb(0xe5, 0xe5, 0xe5),
+            Color::BrightBlack => rgb(0x76, 0x76, 0x76),
+            Color::BrightRed => rgb(0xf1, 0x4c, 0x4c),
+            Color::BrightGreen => rgb(0x23, 0xd1, 0x8b),
+            Color::BrightYellow => rgb(0xf5, 0xf5, 0x43),
+            Color::BrightBlue => rgb(0x3b, 0x8e, 0xea),
+            Color::BrightMagenta => rgb(0xd6, 0x70, 0xd6),
+            Color::BrightCyan => rgb(0x29, 0xb8, 0xdb),
+            Color::BrightWhite => rgb(0xff, 0xff, 0xff),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+}
+
+/// A run of `len` bytes of a parsed line sharing one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub len: usize,
+    pub style: Style,
+}
+
+/// Split `line` into its plain text and the styled spans covering it.
+/// All escape sequences are removed, but only SGR codes affect styling.
+/// Lines without any styling have no spans.
+pub fn parse(line: &str) -> (String, Vec<Span>) {
+    if !line.contains('\x1b') {
+        return (line.to_string(), Vec::new());
+    }
+
+    let mut text = String::with_capacity(line.len());
+    let mut spans = Vec::<Span>::new();
+    let mut style = Style::default();
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            match spans.last_mut() {
+                Some(span) if span.style == style => span.len += c.len_utf8(),
+                _ => spans.push(Span {
+                    len: c.len_utf8(),
+                    style,
+                }),
+            }
+            text.push(c);
+            continue;
+        }
+
+        // Only CSI sequences (ESC [ params final) are understood. Anything
+        // else just drops the ESC and the character after it.
+        if chars.next() != Some('[') {
+            continue;
+        }
+        let mut params = String::new();
+        let mut final_byte = None;
+        for c in chars.by_ref() {
+            if ('\x40'..='\x7e').contains(&c) {
+                final_byte = Some(c);
+                break;
+            }
+            params.push(c);
+        }
+        if final_byte == Some('m') {
+            apply_sgr(&mut style, &params);
+        }
+    }
+
+    if spans.iter().all(|span| span.style == Style::default()) {
+        spans.clear();
+    }
+
+    (text, spans)
+}
+
+/// Update `style` with the SGR parameters `params`, e.g. `1;31`
+fn apply_sgr(style: &mut Style, params: &str) {
+    let mut codes = params
+        .split([';', ':'])
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+
+    // An empty parameter list means reset
+    if params.is_empty() {
+        *style = Style::default();
+        return;
+    }
+
+    while let Some(code) = codes.next() {
+        match code {
+            0 => *style = Style::default(),
+            1 => style.bold = true,
+            22 => style.bold = false,
+            30..=37 => style.fg = Some(Color::ALL[usize::from(code - 30)]),
+            39 => style.fg = None,
+            90..=97 => style.fg = Some(Color::ALL[usize::from(code - 90 + 8)]),
+            // Extended colors aren't supported, but their arguments
+            // mustn't be read as codes
+            38 | 48 => match codes.next() {
+                Some(5) => {
+                    codes.next();
+                }
+                Some(2) => {
+                    codes.nth(2);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Replace each tab in `text` with spaces up to the next tab stop, every
+/// `tab_width` columns, growing the spans covering them to match.
+/// Columns are counted in chars, which suits most monospace output.
+pub fn expand_tabs(text: &str, spans: &mut [Span], tab_width: usize) -> String {
+    if tab_width == 0 || !text.contains('\t') {
+        return text.to_string();
+    }
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    // The span covering `pos`, and where it ends, in bytes of `text`
+    let mut span = 0;
+    let mut span_end = spans.first().map_or(usize::MAX, |span| span.len);
+    for (pos, c) in text.char_indices() {
+        while pos >= span_end && span + 1 < spans.len() {
+            span += 1;
+            span_end += spans[span].len;
+        }
+        if c == '\t' {
+            let n = tab_width - column % tab_width;
+            expanded.extend(std::iter::repeat_n(' ', n));
+            column += n;
+            if let Some(span) = spans.get_mut(span) {
+                span.len += n - 1;
+            }
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sgr_codes_become_spans() {
+        let (text, spans) = parse("\x1b[1;31merror\x1b[0m: \x1b[32mok\x1b[m");
+        assert_eq!(text, "error: ok");
+        assert_eq!(
+            spans,
+            [
+                Span {
+                    len: 5,
+                    style: Style {
+                        fg: Some(Color::Red),
+                        bold: true
+                    }
+                },
+                Span {
+                    len: 2,
+                    style: Style::default()
+                },
+                Span {
+                    len: 2,
+                    style: Style {
+                        fg: Some(Color::Green),
+                        bold: false
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn other_escapes_are_removed() {
+        // Cursor movement, an extended color and a bare reset
+        let (text, spans) = parse("\x1b[2Kdone \x1b[38;5;196mx\x1b[0m");
+        assert_eq!(text, "done x");
+        assert!(spans.is_empty());
+
+        assert_eq!(parse("plain"), ("plain".to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn tabs_are_expanded_to_the_next_stop() {
+        assert_eq!(expand_tabs("a\tbc\td", &mut [], 4), "a   bc  d");
+        assert_eq!(expand_tabs("\t\tx", &mut [], 2), "    x");
+        assert_eq!(expand_tabs("a\tb", &mut [], 0), "a\tb");
+
+        let (text, mut spans) = parse("\x1b[31mab\t\x1b[0mc\td");
+        assert_eq!(expand_tabs(&text, &mut spans, 8), "ab      c       d");
+        let lens = spans.iter().map(|span| span.len).collect::<Vec<_>>();
+        assert_eq!(lens, [8, 9]);
+    }
+}
@@ -0,0 +1,436 @@
+//! An HTTP API for starting and stopping runners from other tools.
+//!
+//! Requests are handed to the app as messages, each carrying a reply
+//! channel for the response:
+//!
+//! - `GET /runners` lists every runner and its status
+//! - `POST /runners/{name}/start` runs a runner
+//! - `POST /runners/{name}/stop` kills a runner
+//! - `POST /runners` adds a runner, given as a JSON runner config
+//! - `DELETE /runners/{name}` kills and removes a runner
+//!
+//! There's no authentication, so requests a browser could have sent on
+//! behalf of some other site are refused: see [`check_first_party`].
+//! Runners are only added from `application/json` bodies, which browsers
+//! won't send cross-origin without a CORS preflight we never answer.
+
+use iced::futures::SinkExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Request heads bigger than this are refused
+const MAX_HEAD_LEN: usize = 8192;
+/// Request bodies bigger than this are refused
+const MAX_BODY_LEN: usize = 64 * 1024;
+
+/// A request as read off the wire, before it's routed
+struct HttpRequest {
+    method: String,
+    path: String,
+    /// The request line and headers
+    head: String,
+    body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    List,
+    Start(String),
+    Stop(String),
+    /// Add the runner configured by the JSON body
+    Add(String),
+    Remove(String),
+}
+
+impl Request {
+    /// The request for `method` and `path`, if it's one we serve
+    fn parse(method: &str, path: &str, body: String) -> Option<Request> {
+        let segments = path
+            .trim_matches('/')
+            .split('/')
+            .map(percent_decode)
+            .collect::<Vec<_>>();
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        match (method, segments.as_slice()) {
+            ("GET", ["runners"]) => Some(Request::List),
+            ("POST", ["runners"]) => Some(Request::Add(body)),
+            ("DELETE", ["runners", name]) => Some(Request::Remove(name.to_string())),
+            ("POST", ["runners", name, "start"]) => Some(Request::Start(name.to_string())),
+            ("POST", ["runners", name, "stop"]) => Some(Request::Stop(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    pub fn ok(body: serde_json::Value) -> Response {
+        Response { status: 200, body }
+    }
+
+    pub fn error(status: u16, error: impl std::fmt::Display) -> Response {
+        Response {
+            status,
+            body: serde_json::json!({ "error": error.to_string() }),
+        }
+    }
+}
+
+/// A request waiting for the app to respond
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub request: Request,
+    // Messages must be Clone, but there's only one reply
+    reply: std::sync::Arc<std::sync::Mutex<Option<tokio::sync::oneshot::Sender<Response>>>>,
+}
+
+impl Call {
+    pub fn reply(&self, response: Response) {
+        if let Some(reply) = self.reply.lock().unwrap().take() {
+            let _ = reply.send(response);
+        }
+    }
+}
+
+/// Serve the API on `addr`, producing a `Call` for each request
+pub fn subscription(addr: std::net::SocketAddr) -> iced::Subscription<Call> {
+    iced::Subscription::run_with_id(
+        addr,
+        iced::stream::channel(100, move |calls| async move {
+            let listener = match tokio::net::TcpListener::bind(addr).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    tracing::error!("unable to listen on {addr}: {err}");
+                    return;
+                }
+            };
+            tracing::info!("listening on {addr}");
+            if !addr.ip().is_loopback() {
+                tracing::warn!(
+                    "the API has no authentication, so anyone who can reach {addr} can run commands"
+                );
+            }
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle(stream, calls.clone()));
+                    }
+                    Err(err) => tracing::warn!("accept failed: {err}"),
+                }
+            }
+        }),
+    )
+}
+
+/// Answer one request on `stream`, then close it
+async fn handle(
+    mut stream: tokio::net::TcpStream,
+    mut calls: iced::futures::channel::mpsc::Sender<Call>,
+) {
+    let Ok(local_addr) = stream.local_addr() else {
+        return;
+    };
+    let response = match read_request(&mut stream).await {
+        Some(http) => match check_first_party(&http.head, local_addr) {
+            Ok(()) => match Request::parse(&http.method, &http.path, http.body) {
+                Some(Request::Add(_)) if !is_json(&http.head) => {
+                    Response::error(415, "runners must be given as application/json")
+                }
+                Some(request) => {
+                    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+                    let call = Call {
+                        request,
+                        reply: std::sync::Arc::new(std::sync::Mutex::new(Some(reply_tx))),
+                    };
+                    if calls.send(call).await.is_err() {
+                        return;
+                    }
+                    reply_rx
+                        .await
+                        .unwrap_or_else(|_| Response::error(500, "no response"))
+                }
+                None => Response::error(404, format!("no route for {} {}", http.method, http.path)),
+            },
+            Err(refused) => refused,
+        },
+        None => Response::error(400, "bad request"),
+    };
+
+    let body = response.body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Refuse requests a browser could have sent on behalf of another site.
+/// Browsers send an `Origin` with cross-origin requests, even the simple
+/// ones that skip the CORS preflight, and a `Host` naming that site's
+/// domain after DNS rebinding, so only a `Host` naming the address we're
+/// bound to is accepted.
+fn check_first_party(head: &str, local_addr: std::net::SocketAddr) -> Result<(), Response> {
+    if header(head, "origin").is_some() {
+        return Err(Response::error(403, "cross-origin requests are refused"));
+    }
+    let ours = header(head, "host").is_some_and(|host| {
+        host == local_addr.to_string()
+            || (local_addr.ip().is_loopback() && host == format!("localhost:{}", local_addr.port()))
+    });
+    if !ours {
+        return Err(Response::error(403, format!("Host must be {local_addr}")));
+    }
+    Ok(())
+}
+
+/// The request on `stream`
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<HttpRequest> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    let head_len = loop {
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 || request.len() + n > MAX_HEAD_LEN {
+            return None;
+        }
+        request.extend_from_slice(&buf[..n]);
+    };
+    let head = std::str::from_utf8(&request[..head_len]).ok()?.to_string();
+    let (method, path) = parse_request_line(&head)?;
+    let body_len = content_length(&head)?;
+    if body_len > MAX_BODY_LEN {
+        return None;
+    }
+
+    // Some of the body may have been read with the head
+    let mut body = request.split_off(head_len);
+    while body.len() < body_len {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    body.truncate(body_len);
+    Some(HttpRequest {
+        method,
+        path,
+        head,
+        body: String::from_utf8(body).ok()?,
+    })
+}
+
+fn parse_request_line(head: &str) -> Option<(String, String)> {
+    let mut parts = head.lines().next()?.split(' ');
+    let method = parts.next()?;
+    let target = parts.next()?;
+    let path = target.split('?').next()?;
+    Some((method.to_string(), path.to_string()))
+}
+
+/// The `Content-Length` of `head`, 0 if it has none, or None if it's
+/// invalid
+fn content_length(head: &str) -> Option<usize> {
+    match header(head, "content-length") {
+        Some(value) => value.parse().ok(),
+        None => Some(0),
+    }
+}
+
+/// Whether `head` says its body is JSON
+fn is_json(head: &str) -> bool {
+    header(head, "content-type").is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+    })
+}
+
+/// The value of the first header in `head` called `name`, ignoring case
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (line_name, value) = line.split_once(':')?;
+        line_name.eq_ignore_ascii_case(name).then_some(value.trim())
+    })
+}
+
+/// Decode `%XX` escapes, so names with spaces can be used
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn routes_are_parsed() {
+        let parse = |method, path| Request::parse(method, path, "{}".to_string());
+        assert_eq!(parse("GET", "/runners"), Some(Request::List));
+        assert_eq!(parse("GET", "/runners/"), Some(Request::List));
+        assert_eq!(
+            parse("POST", "/runners/web%20server/start"),
+            Some(Request::Start("web server".to_string()))
+        );
+        assert_eq!(
+            parse("POST", "/runners/db/stop"),
+            Some(Request::Stop("db".to_string()))
+        );
+        assert_eq!(
+            parse("POST", "/runners"),
+            Some(Request::Add("{}".to_string()))
+        );
+        assert_eq!(
+            parse("DELETE", "/runners/db"),
+            Some(Request::Remove("db".to_string()))
+        );
+        assert_eq!(parse("GET", "/runners/db/stop"), None);
+        assert_eq!(parse("POST", "/runners/db"), None);
+
+        assert_eq!(
+            parse_request_line("POST /runners/db/start?x=1 HTTP/1.1\r\nHost: a\r\n\r\n"),
+            Some(("POST".to_string(), "/runners/db/start".to_string()))
+        );
+        assert_eq!(
+            content_length("POST / HTTP/1.1\r\ncontent-length: 12\r\n"),
+            Some(12)
+        );
+        assert_eq!(content_length("GET / HTTP/1.1\r\nHost: a\r\n"), Some(0));
+        assert_eq!(
+            content_length("POST / HTTP/1.1\r\nContent-Length: -1\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn calls_are_answered_over_http() {
+        use iced::futures::StreamExt;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (calls_tx, mut calls_rx) = iced::futures::channel::mpsc::channel(1);
+
+            let server = tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                handle(stream, calls_tx).await;
+            });
+            let app = tokio::spawn(async move {
+                let call = calls_rx.next().await.unwrap();
+                assert_eq!(call.request, Request::Add(r#"{"name":"db"}"#.to_string()));
+                call.reply(Response::error(409, "already exists"));
+            });
+
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let request = format!(
+                "POST /runners HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: 13\r\n\r\n{{\"name\":\"db\"}}"
+            );
+            client.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+
+            server.await.unwrap();
+            app.await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 409 Conflict\r\n"));
+            assert!(response.ends_with(r#"{"error":"already exists"}"#));
+        });
+    }
+
+    #[test]
+    fn cross_site_requests_are_refused() {
+        use iced::futures::StreamExt;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let refused = [
+                (
+                    "/runners/db/start",
+                    format!("Host: {addr}\r\nOrigin: https://example.com\r\n"),
+                    "403 Forbidden",
+                ),
+                (
+                    "/runners/db/start",
+                    format!("Host: example.com:{}\r\n", addr.port()),
+                    "403 Forbidden",
+                ),
+                ("/runners/db/start", String::new(), "403 Forbidden"),
+                // As a form or fetch without a preflight would send it
+                (
+                    "/runners",
+                    format!("Host: {addr}\r\nContent-Type: text/plain\r\n"),
+                    "415 Unsupported Media Type",
+                ),
+            ];
+            for (path, headers, status) in refused {
+                let (calls_tx, mut calls_rx) = iced::futures::channel::mpsc::channel(1);
+                let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+                let (stream, _) = listener.accept().await.unwrap();
+                let request = format!("POST {path} HTTP/1.1\r\n{headers}\r\n");
+                client.write_all(request.as_bytes()).await.unwrap();
+                handle(stream, calls_tx).await;
+
+                let mut response = String::new();
+                client.read_to_string(&mut response).await.unwrap();
+                assert!(response.starts_with(&format!("HTTP/1.1 {status}\r\n")));
+                assert!(calls_rx.next().await.is_none());
+            }
+        });
+
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        assert!(check_first_party("GET / HTTP/1.1\r\nhost: localhost:7878\r\n", addr).is_ok());
+    }
+}
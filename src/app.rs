@@ -1,13 +1,112 @@
+use crate::ansi;
 use crate::runner::{self, Runner};
 
 use iced::widget::{self, Column, Row};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::time::SystemTime;
 
+pub use scroll_state::Follow;
+
+/// A committed log line: when it was logged, its text with escape codes
+/// removed, the ANSI styling of that text, and its position among every
+/// runner's lines, which breaks ties between equal times
+pub type Log = (SystemTime, IO, Vec<ansi::Span>, u64);
+
+/// Identifies a runner for as long as it exists. Its index can change,
+/// as runners before it are removed, and is never reused.
+pub type RunnerId = usize;
+
 pub struct App {
     runners: Vec<Runner>,
+    runner_ids: Vec<RunnerId>,
+    /// Given to the next runner added
+    #[cfg(feature = "api")]
+    next_runner_id: RunnerId,
     runner_stdout_buf: Vec<String>,
     runner_stderr_buf: Vec<String>,
-    logs: Vec<Vec<(SystemTime, IO)>>, // log[runner_id][log_item]
+    logs: Vec<VecDeque<Log>>,         // log[runner_id][log_item]
+    dependencies: Vec<Vec<usize>>,    // dependencies[runner_id][dependency]
+    run_starts: Vec<Vec<SystemTime>>, // run_starts[runner_id][run]
+    /// Times each collapsed line was logged in a row, by its sequence
+    /// number, for lines logged more than once
+    repeats: HashMap<u64, usize>,
+    /// When each runner last wrote to stdout or stderr
+    last_output: Vec<Option<SystemTime>>,
+    /// Runners that have gone quiet for longer than their `stall_timeout`
+    stalled: Vec<bool>,
+    usage_monitor: crate::usage::Monitor,
+    /// Lines per second for each runner, over the last minute
+    output_rates: Vec<runner::output_rate::OutputRate>,
+    tag_filter: Option<String>,
+    /// Only runners whose name, description or tags contain this are
+    /// listed. Their logs are shown regardless.
+    runner_query: String,
+    /// Order of the runner list. Runners keep their indices underneath.
+    runner_sort: RunnerSort,
+    timestamp_mode: TimestampMode,
+    /// Show each row's position in the merged timeline
+    line_numbers: bool,
+    /// Wrap long lines instead of scrolling sideways
+    wrap_lines: bool,
+    log_search: LogSearch,
+    /// Format used by the export buttons
+    export_format: ExportFormat,
+    /// Write the whole log history here when the window is closed
+    pub log_export_on_exit: Option<std::path::PathBuf>,
+    /// Save UI state here on exit, see [`crate::session`]
+    pub session_path: Option<std::path::PathBuf>,
+    pub theme: iced::Theme,
+    /// Font for logs and other monospace text
+    pub mono_font: iced::Font,
+    /// Drawn between a row's runner name and its line, by stream
+    pub stdout_glyph: String,
+    pub stderr_glyph: String,
+    /// Drawn in place of the default text color for stderr rows
+    pub stderr_color: Option<iced::Color>,
+    /// Columns between tab stops, which tabs in lines are expanded to
+    pub tab_width: usize,
+    /// Last known logical size and position of the window, for saving
+    window_size: Option<iced::Size>,
+    window_position: Option<iced::Point>,
+    /// Bumped on each resize or move, so only the last of a burst saves
+    /// the session
+    window_changes: u64,
+    /// Rows starting with one of these level tokens are drawn in its
+    /// color. Empty when level coloring is off.
+    pub level_colors: Vec<(String, iced::Color)>,
+    /// Show a desktop notification when a runner exits with an error
+    pub notify_on_failure: bool,
+    /// Serve the HTTP API here
+    #[cfg(feature = "api")]
+    pub api_addr: Option<std::net::SocketAddr>,
+    /// Where runners added through the API read their `script_file` from,
+    /// as if they were in the config
+    #[cfg(feature = "api")]
+    pub config_dir: std::path::PathBuf,
+    /// Print each line to our stdout or stderr, prefixed with its runner's
+    /// name, for running without the UI
+    pub echo_lines: bool,
+    /// Longest runner name, which echoed names are padded to
+    name_width: usize,
+    /// Sequence number of the next line committed
+    next_seq: u64,
+    /// Stop all was pressed, and is waiting to be confirmed
+    confirm_stop_all: bool,
+    /// A runner and the run its stop button was pressed for, waiting for
+    /// the kill to be confirmed
+    confirm_kill: Option<(usize, SystemTime)>,
+    /// The only runner shown in the log pane, ignoring `show_logs` and
+    /// the tag filter, which come back into play once it's unset
+    solo: Option<usize>,
+    /// The window to close once every runner has stopped
+    closing: Option<iced::window::Id>,
+    /// Share of the window's width given to the runner panel
+    split: f32,
+    /// The divider between the runners and the logs is being dragged
+    split_dragging: bool,
+    /// Hide the runner panel, giving the log pane the whole window
+    runners_collapsed: bool,
 
     scroll_state: scroll_state::ScrollState,
 }
@@ -18,54 +117,1102 @@ pub enum IO {
     Stderr(String),
 }
 
-#[derive(Debug)]
+impl IO {
+    pub fn line(&self) -> &str {
+        match self {
+            IO::Stdout(line) | IO::Stderr(line) => line,
+        }
+    }
+}
+
+/// How log lines are timestamped in the log pane
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimestampMode {
+    #[default]
+    Off,
+    /// Local time of day the line was logged
+    Absolute,
+    /// Time since the start of the run that logged the line
+    Relative,
+}
+
+impl TimestampMode {
+    fn next(self) -> TimestampMode {
+        match self {
+            TimestampMode::Off => TimestampMode::Absolute,
+            TimestampMode::Absolute => TimestampMode::Relative,
+            TimestampMode::Relative => TimestampMode::Off,
+        }
+    }
+}
+
+/// Order runners are listed in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RunnerSort {
+    /// As they appear in the config
+    #[default]
+    Config,
+    Name,
+    /// Running first, then failed, then the rest
+    Status,
+}
+
+impl RunnerSort {
+    fn next(self) -> RunnerSort {
+        match self {
+            RunnerSort::Config => RunnerSort::Name,
+            RunnerSort::Name => RunnerSort::Status,
+            RunnerSort::Status => RunnerSort::Config,
+        }
+    }
+}
+
+/// File format for exported logs
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExportFormat {
+    /// `timestamp runner glyph text` per line
+    #[default]
+    Text,
+    /// One JSON object per line, with `ts`, `runner`, `stream` and `line`
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// JSON lines for `.jsonl` and `.ndjson` files, text otherwise
+    pub fn from_path(path: &std::path::Path) -> ExportFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl" | "ndjson") => ExportFormat::JsonLines,
+            _ => ExportFormat::Text,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "log",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// What's typed in the log search box, and how to interpret it
+#[derive(Default)]
+struct LogSearch {
+    query: String,
+    regex: bool,
+    case_insensitive: bool,
+    streams: scroll_state::Streams,
+    /// Why `query` couldn't be used, leaving the previous pattern active
+    error: Option<String>,
+}
+
+impl LogSearch {
+    /// The pattern to filter by, or nothing for an empty query
+    fn pattern(&self) -> Result<Option<regex::Regex>, regex::Error> {
+        if self.query.is_empty() {
+            return Ok(None);
+        }
+
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        regex::RegexBuilder::new(&pattern)
+            .case_insensitive(self.case_insensitive)
+            .build()
+            .map(Some)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Message {
     Runner(usize, runner::Message),
+    /// A message from one of a runner's tasks. These can arrive after
+    /// runners have been added or removed, so they're sent by id.
+    RunnerTask(RunnerId, runner::Message),
+    #[cfg(feature = "api")]
+    Api(crate::api::Call),
     ScrollState(scroll_state::Message),
+    SetTagFilter(Option<String>),
+    SetRunnerQuery(String),
+    SetRunnerSort(RunnerSort),
+    /// Run every runner that isn't running or about to
+    RunAll,
+    /// Run again every runner whose last run failed, with fresh backoff
+    RunFailed,
+    /// Ask to stop every runner
+    StopAll,
+    /// Stop every runner if true, or forget about stopping them
+    ConfirmStopAll(bool),
+    /// Kill the runner waiting for confirmation if true, or leave it be
+    ConfirmKill(bool),
+    ClearLogs,
+    CopyLogs,
+    /// Write every runner's whole log history to a file
+    ExportLogs(std::path::PathBuf),
+    /// Write the lines the log pane would show to a file
+    ExportFilteredLogs(std::path::PathBuf),
+    SetExportFormat(ExportFormat),
+    OpenUrl(String),
+    WindowCloseRequested(iced::window::Id),
+    /// Runners took too long to stop, so close the window regardless
+    CloseTimedOut(iced::window::Id),
+    SetTheme(iced::Theme),
+    WindowOpened(Option<iced::Point>, iced::Size),
+    WindowResized(iced::Size),
+    WindowMoved(iced::Point),
+    /// The window has kept its size and position since change `n`
+    WindowSettled(u64),
+    SetLogFilter(String),
+    SetLogFilterRegex(bool),
+    SetLogFilterCaseInsensitive(bool),
+    SetLogFilterStreams(scroll_state::Streams),
+    SetTimestampMode(TimestampMode),
+    SetLineNumbers(bool),
+    SetWrapLines(bool),
+    /// Files a runner watches have changed
+    WatchTriggered(RunnerId),
+    /// A runner's scheduled run is due, at the given time
+    ScheduleDue(RunnerId, SystemTime),
+    SetRunnersCollapsed(bool),
+    SplitDragStarted,
+    /// The cursor moved to this x coordinate while dragging the divider
+    SplitDragged(f32),
+    SplitDragEnded,
+    Tick,
 }
 
-const GLYPH_STDOUT: &str = "[>]";
-const GLYPH_STDERR: &str = "[!]";
+/// Share of the window's width given to the runner panel at first
+const DEFAULT_SPLIT: f32 = 0.3;
+/// Neither side of the divider can be dragged narrower than this share
+const MIN_SPLIT: f32 = 0.1;
+
+const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// How long the window must be left alone before its size and position
+/// are saved, so dragging it saves only once
+const WINDOW_SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub const GLYPH_STDOUT: &str = "[>]";
+pub const GLYPH_STDERR: &str = "[!]";
 
 impl App {
     pub fn new(runners: Vec<Runner>) -> App {
         let runner_stdout_buf = vec![String::new(); runners.len()];
         let runner_stderr_buf = vec![String::new(); runners.len()];
-        let logs = vec![Vec::new(); runners.len()];
+        let logs = vec![VecDeque::new(); runners.len()];
+        let runners_len = runners.len();
+        let dependencies = dependencies(&runners);
+        let name_width = name_width(&runners);
         App {
             runners,
+            runner_ids: (0..runners_len).collect(),
+            #[cfg(feature = "api")]
+            next_runner_id: runners_len,
             runner_stdout_buf,
             runner_stderr_buf,
             logs,
+            dependencies,
+            run_starts: vec![Vec::new(); runners_len],
+            repeats: HashMap::new(),
+            last_output: vec![None; runners_len],
+            stalled: vec![false; runners_len],
+            usage_monitor: crate::usage::Monitor::new(),
+            output_rates: (0..runners_len)
+                .map(|_| runner::output_rate::OutputRate::new(std::time::Instant::now()))
+                .collect(),
+            tag_filter: None,
+            runner_query: String::new(),
+            runner_sort: RunnerSort::default(),
+            timestamp_mode: TimestampMode::default(),
+            line_numbers: false,
+            wrap_lines: false,
+            log_search: LogSearch::default(),
+            export_format: ExportFormat::default(),
+            log_export_on_exit: None,
+            session_path: None,
+            theme: iced::Theme::default(),
+            mono_font: iced::Font::MONOSPACE,
+            stdout_glyph: GLYPH_STDOUT.to_string(),
+            stderr_glyph: GLYPH_STDERR.to_string(),
+            stderr_color: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            window_size: None,
+            window_position: None,
+            window_changes: 0,
+            level_colors: Vec::new(),
+            echo_lines: false,
+            name_width,
+            next_seq: 0,
+            confirm_stop_all: false,
+            confirm_kill: None,
+            solo: None,
+            closing: None,
+            split: DEFAULT_SPLIT,
+            split_dragging: false,
+            runners_collapsed: false,
+            notify_on_failure: false,
+            #[cfg(feature = "api")]
+            api_addr: None,
+            #[cfg(feature = "api")]
+            config_dir: std::path::PathBuf::from("."),
             scroll_state: scroll_state::ScrollState::new(),
         }
     }
 
-    pub fn view(&self) -> iced::Element<'_, Message> {
-        let runners = Column::from_iter(
+    /// Draw this many lines beyond each edge of the log pane
+    pub fn set_log_overscan(&mut self, overscan: usize) {
+        self.scroll_state.overscan = overscan;
+    }
+
+    /// Tell the log pane the text size set for the application, which
+    /// sets how tall its rows are
+    pub fn set_text_size(&mut self, text_size: f32) {
+        self.scroll_state.text_size = text_size;
+    }
+
+    /// Stop every runner for good, e.g. before exiting
+    pub fn shut_down(&mut self) -> iced::Task<Message> {
+        iced::Task::batch(
+            self.runners
+                .iter_mut()
+                .zip(&self.runner_ids)
+                .map(|(runner, &id)| {
+                    runner
+                        .shut_down()
+                        .map(move |msg| Message::RunnerTask(id, msg))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Save the session and export the logs, as asked, then close window `id`
+    fn close(&mut self, id: iced::window::Id) -> iced::Task<Message> {
+        self.closing = None;
+        if let Some(path) = &self.session_path {
+            crate::session::save(path, &self.session());
+        }
+        if let Some(path) = &self.log_export_on_exit {
+            let logs = self.all_logs();
+            let result = self.export_logs(path, ExportFormat::from_path(path), &logs);
+            App::report_export(path, logs.len(), result);
+        }
+        iced::window::close(id)
+    }
+
+    /// Save the session once the window has settled after this change
+    fn window_changed(&mut self) -> iced::Task<Message> {
+        self.window_changes += 1;
+        let n = self.window_changes;
+        iced::Task::perform(
+            async move { tokio::time::sleep(WINDOW_SAVE_DELAY).await },
+            move |()| Message::WindowSettled(n),
+        )
+    }
+
+    /// The current index of the runner with `id`, if it hasn't been removed
+    fn runner_index(&self, id: RunnerId) -> Option<usize> {
+        self.runner_ids
+            .iter()
+            .position(|&runner_id| runner_id == id)
+    }
+
+    /// Add `runner` after the others, starting it if it's `auto_start`
+    #[cfg(feature = "api")]
+    fn add_runner(&mut self, runner: Runner) -> iced::Task<Message> {
+        let auto_start = runner.auto_start;
+        self.runners.push(runner);
+        self.runner_ids.push(self.next_runner_id);
+        self.next_runner_id += 1;
+        self.runner_stdout_buf.push(String::new());
+        self.runner_stderr_buf.push(String::new());
+        self.logs.push(VecDeque::new());
+        self.run_starts.push(Vec::new());
+        self.last_output.push(None);
+        self.stalled.push(false);
+        self.output_rates.push(runner::output_rate::OutputRate::new(
+            std::time::Instant::now(),
+        ));
+        self.dependencies = dependencies(&self.runners);
+        self.name_width = name_width(&self.runners);
+
+        let i = self.runners.len() - 1;
+        let run = if auto_start {
+            self.update(Message::Runner(i, runner::Message::ScriptRun))
+        } else {
+            iced::Task::none()
+        };
+        let schedule = self.schedule_next(i, SystemTime::now());
+        iced::Task::batch([self.update_runner_idxs(), run, schedule])
+    }
+
+    /// Remove runner `i` and its logs. Dropping a running runner kills its
+    /// script, and anything its tasks send after is ignored.
+    #[cfg(feature = "api")]
+    fn remove_runner(&mut self, i: usize) -> iced::Task<Message> {
+        let runner = self.runners.remove(i);
+        tracing::info!(runner = %runner.name, "removed");
+        for (.., seq) in &self.logs[i] {
+            self.repeats.remove(seq);
+        }
+        self.runner_ids.remove(i);
+        self.runner_stdout_buf.remove(i);
+        self.runner_stderr_buf.remove(i);
+        self.logs.remove(i);
+        self.run_starts.remove(i);
+        self.last_output.remove(i);
+        self.stalled.remove(i);
+        self.output_rates.remove(i);
+        self.dependencies = dependencies(&self.runners);
+        self.name_width = name_width(&self.runners);
+
+        // Indices past the removed runner move down by one
+        let shift = |j: usize| match j.cmp(&i) {
+            std::cmp::Ordering::Less => Some(j),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(j - 1),
+        };
+        self.solo = self.solo.and_then(shift);
+        self.confirm_kill = self
+            .confirm_kill
+            .and_then(|(j, start_time)| Some((shift(j)?, start_time)));
+        self.scroll_state.remove_runner(i);
+        self.update_runner_idxs()
+    }
+
+    /// Whether no runner is running or about to run. Runners waiting on
+    /// dependencies don't count, as nothing may ever make them ready.
+    pub fn is_idle(&self) -> bool {
+        !self.runners.iter().any(Runner::is_active)
+    }
+
+    /// Respond to an API request, starting or stopping runners as asked
+    #[cfg(feature = "api")]
+    fn answer(&mut self, call: crate::api::Call) -> iced::Task<Message> {
+        use crate::api::{Request, Response};
+
+        let find = |name: &str| self.runners.iter().position(|r| r.name == name);
+        let (response, task) = match &call.request {
+            Request::List => {
+                let runners = self
+                    .runners
+                    .iter()
+                    .map(|runner| {
+                        serde_json::json!({
+                            "name": runner.name,
+                            "status": runner.status_name(),
+                            "pid": runner.pid(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (Response::ok(runners.into()), iced::Task::none())
+            }
+            Request::Start(name) => match find(name) {
+                Some(i) if self.runners[i].is_active() => {
+                    (Response::error(409, "already running"), iced::Task::none())
+                }
+                Some(i) => {
+                    let task = self.update(Message::Runner(i, runner::Message::ScriptRunManual));
+                    let status = self.runners[i].status_name();
+                    (Response::ok(serde_json::json!({ "status": status })), task)
+                }
+                None => (Response::error(404, "no such runner"), iced::Task::none()),
+            },
+            Request::Stop(name) => match find(name) {
+                Some(i) => match self.runners[i].start_time() {
+                    Some(start_time) => {
+                        let message = runner::Message::ScriptKill { start_time };
+                        let task = self.update(Message::Runner(i, message));
+                        (
+                            Response::ok(serde_json::json!({ "status": "stopping" })),
+                            task,
+                        )
+                    }
+                    None => (Response::error(409, "not running"), iced::Task::none()),
+                },
+                None => (Response::error(404, "no such runner"), iced::Task::none()),
+            },
+            Request::Add(body) => match crate::config::parse_runner(body, &self.config_dir) {
+                Ok(config) if find(&config.name).is_some() => {
+                    (Response::error(409, "already exists"), iced::Task::none())
+                }
+                Ok(config) => match config
+                    .depends_on
+                    .iter()
+                    .find(|dependency| find(dependency).is_none())
+                {
+                    Some(dependency) => (
+                        Response::error(400, format!("no such runner {dependency:?}")),
+                        iced::Task::none(),
+                    ),
+                    None => {
+                        let task = self.add_runner(Runner::from(config));
+                        let status = self.runners.last().unwrap().status_name();
+                        (Response::ok(serde_json::json!({ "status": status })), task)
+                    }
+                },
+                Err(err) => (Response::error(400, err), iced::Task::none()),
+            },
+            Request::Remove(name) => match find(name) {
+                Some(i) => {
+                    let dependents = self
+                        .runners
+                        .iter()
+                        .filter(|runner| runner.depends_on.contains(name))
+                        .map(|runner| runner.name.as_str())
+                        .collect::<Vec<_>>();
+                    if dependents.is_empty() {
+                        let task = self.remove_runner(i);
+                        (
+                            Response::ok(serde_json::json!({ "status": "removed" })),
+                            task,
+                        )
+                    } else {
+                        let error = format!("needed by {}", dependents.join(", "));
+                        (Response::error(409, error), iced::Task::none())
+                    }
+                }
+                None => (Response::error(404, "no such runner"), iced::Task::none()),
+            },
+        };
+        call.reply(response);
+        task
+    }
+
+    pub fn theme(&self) -> iced::Theme {
+        self.theme.clone()
+    }
+
+    /// How many runners are running, and how many have failed
+    fn status_counts(&self) -> (usize, usize) {
+        let running = self.runners.iter().filter(|r| r.is_running()).count();
+        let failed = self.runners.iter().filter(|r| r.failed()).count();
+        (running, failed)
+    }
+
+    /// The window title, summing up runner statuses
+    pub fn title(&self) -> String {
+        let counts = match self.status_counts() {
+            (0, 0) => return "Battlestation".to_string(),
+            (running, 0) => format!("{running} running"),
+            (0, failed) => format!("{failed} failed"),
+            (running, failed) => format!("{running} running, {failed} failed"),
+        };
+        format!("Battlestation \u{2014} {counts}")
+    }
+
+    /// Whether every runner's last run exited successfully
+    pub fn all_succeeded(&self) -> bool {
+        self.runners.iter().all(Runner::succeeded)
+    }
+
+    /// Start every runner, regardless of `auto_start`
+    pub fn start_all(&mut self) -> iced::Task<Message> {
+        iced::Task::batch(
+            (0..self.runners.len())
+                .map(|i| self.update(Message::Runner(i, runner::Message::ScriptRun)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Run every runner that isn't running or about to. Runners with
+    /// dependencies wait for them, as when run by hand.
+    fn run_all(&mut self) -> iced::Task<Message> {
+        let idxs = (0..self.runners.len())
+            .filter(|&i| self.runners[i].can_run())
+            .collect::<Vec<_>>();
+        iced::Task::batch(
+            idxs.into_iter()
+                .map(|i| self.update(Message::Runner(i, runner::Message::ScriptRunManual)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Run every runner whose last run failed, forgetting earlier
+    /// failures so their restart backoff starts again from nothing
+    fn run_failed(&mut self) -> iced::Task<Message> {
+        let idxs = (0..self.runners.len())
+            .filter(|&i| self.runners[i].failed() && self.runners[i].can_run())
+            .collect::<Vec<_>>();
+        iced::Task::batch(
+            idxs.into_iter()
+                .map(|i| {
+                    self.runners[i].reset_backoff();
+                    self.update(Message::Runner(i, runner::Message::ScriptRunManual))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Kill every running runner, and cancel any pending starts
+    fn stop_all(&mut self) -> iced::Task<Message> {
+        let messages = (0..self.runners.len())
+            .filter_map(|i| Some((i, self.runners[i].stop_message()?)))
+            .collect::<Vec<_>>();
+        iced::Task::batch(
+            messages
+                .into_iter()
+                .map(|(i, message)| self.update(Message::Runner(i, message)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// The UI state worth keeping between launches
+    fn session(&self) -> crate::session::Session {
+        crate::session::Session {
+            runners: self
+                .runners
+                .iter()
+                .map(|runner| {
+                    let state = crate::session::RunnerSession {
+                        show_logs: runner.show_logs,
+                        restart: runner.restart,
+                    };
+                    (runner.name.clone(), state)
+                })
+                .collect(),
+            tag_filter: self.tag_filter.clone(),
+            follow: Some(self.scroll_state.follow()),
+            split: Some(self.split),
+            runners_collapsed: self.runners_collapsed,
+            window: self.window_size.map(|size| crate::session::WindowGeometry {
+                width: size.width,
+                height: size.height,
+                position: self.window_position.map(|p| (p.x, p.y)),
+            }),
+        }
+    }
+
+    /// Restore UI state saved by an earlier launch. Runners and tags that
+    /// are gone from the config are ignored.
+    pub fn restore_session(&mut self, session: crate::session::Session) -> iced::Task<Message> {
+        for runner in &mut self.runners {
+            if let Some(state) = session.runners.get(&runner.name) {
+                runner.show_logs = state.show_logs;
+                runner.restart = state.restart;
+            }
+        }
+        // Kept until the window reports its own, in case it never does
+        if let Some(window) = session.window {
+            self.window_size = Some(iced::Size::new(window.width, window.height));
+            self.window_position = window.position.map(|(x, y)| iced::Point::new(x, y));
+        }
+        if let Some(split) = session.split {
+            self.split = clamp_split(split);
+        }
+        self.runners_collapsed = session.runners_collapsed;
+        self.tag_filter = session
+            .tag_filter
+            .filter(|tag| self.runners.iter().any(|r| r.tags.contains(tag)));
+
+        let follow = session.follow.map(|follow| {
+            self.update(Message::ScrollState(scroll_state::Message::SetFollow(
+                follow,
+            )))
+        });
+        iced::Task::batch([self.update_runner_idxs()].into_iter().chain(follow))
+    }
+
+    /// Start every runner marked `auto_start`. Restarts after that are
+    /// still left to each runner's restart policy.
+    pub fn auto_start(&self) -> iced::Task<Message> {
+        iced::Task::batch(
             self.runners
                 .iter()
-                .map(Runner::view)
                 .enumerate()
-                .map(|(i, el)| el.map(move |msg| Message::Runner(i, msg))),
+                .filter(|(_, r)| r.auto_start)
+                .map(|(i, _)| {
+                    let id = self.runner_ids[i];
+                    iced::Task::done(Message::RunnerTask(id, runner::Message::ScriptRun))
+                }),
         )
-        .spacing(10);
+    }
 
-        fn to_row<'a>(name: &'a str, glyph: &'a str, line: &'a str) -> iced::Element<'a, Message> {
-            widget::row![
-                iced::Element::from(widget::text(name).font(iced::Font::MONOSPACE)),
-                iced::Element::from(widget::text(glyph).font(iced::Font::MONOSPACE)),
-                iced::Element::from(widget::text(" ").font(iced::Font::MONOSPACE)),
-                iced::Element::from(widget::text(line).font(iced::Font::MONOSPACE)),
-            ]
-            .into()
+    /// Wait for the first run of every scheduled runner
+    pub fn start_schedules(&self) -> iced::Task<Message> {
+        let now = SystemTime::now();
+        iced::Task::batch((0..self.runners.len()).map(|i| self.schedule_next(i, now)))
+    }
+
+    /// Wait for runner `i`'s next scheduled run after `time`, if it has one
+    fn schedule_next(&self, i: usize, time: SystemTime) -> iced::Task<Message> {
+        let Some(due) = self.runners[i]
+            .schedule
+            .as_ref()
+            .and_then(|schedule| schedule.next_after(time))
+        else {
+            return iced::Task::none();
+        };
+        let wait = due.duration_since(SystemTime::now()).unwrap_or_default();
+        let id = self.runner_ids[i];
+        iced::Task::perform(async move { tokio::time::sleep(wait).await }, move |()| {
+            Message::ScheduleDue(id, due)
+        })
+    }
+
+    /// Commit a complete line to runner `i`'s logs, dropping the oldest
+    /// lines past its `max_log_lines`
+    fn push_line(&mut self, i: usize, io: IO) {
+        if self.echo_lines {
+            use std::io::Write;
+
+            let width = self.name_width;
+            let name = &self.runners[i].name;
+            // Errors are ignored, as println! would panic on a closed pipe
+            // and take the supervisor down with it, e.g. under `| head`
+            let _ = match &io {
+                IO::Stdout(line) => writeln!(std::io::stdout().lock(), "{name:width$} | {line}"),
+                IO::Stderr(line) => writeln!(std::io::stderr().lock(), "{name:width$} | {line}"),
+            };
+        }
+
+        // Tabs are expanded once, so search and copy see what's drawn
+        let parse = |line: &str| {
+            let (line, mut spans) = ansi::parse(line);
+            let line = ansi::expand_tabs(&line, &mut spans, self.tab_width);
+            (line, spans)
+        };
+        let (io, mut spans) = match io {
+            IO::Stdout(line) => {
+                let (line, spans) = parse(&line);
+                (IO::Stdout(line), spans)
+            }
+            IO::Stderr(line) => {
+                let (line, spans) = parse(&line);
+                (IO::Stderr(line), spans)
+            }
+        };
+        if self.runners[i].strip_ansi {
+            spans.clear();
+        }
+
+        let log = &mut self.logs[i];
+        if self.runners[i].collapse_repeats
+            && let Some((_, last_io, last_spans, seq)) = log.back()
+            && *last_io == io
+            && *last_spans == spans
+        {
+            // Nothing's added, the counter is drawn on the existing row
+            *self.repeats.entry(*seq).or_insert(1) += 1;
+            return;
+        }
+        log.push_back((SystemTime::now(), io, spans, self.next_seq));
+        self.next_seq += 1;
+        self.scroll_state
+            .line_added(i, log.len() - 1, &log[log.len() - 1]);
+
+        if let Some(max_log_lines) = self.runners[i].max_log_lines
+            && log.len() > max_log_lines
+        {
+            let n_evicted = log.len() - max_log_lines;
+            for (.., seq) in log.drain(..n_evicted) {
+                self.repeats.remove(&seq);
+            }
+            self.scroll_state.evict(i, n_evicted);
+        }
+    }
+
+    /// Commit runner `i`'s partial lines to its logs.
+    /// Returns whether there was anything to commit.
+    fn flush_partial_lines(&mut self, i: usize) -> bool {
+        let stdout = partial_line(&std::mem::take(&mut self.runner_stdout_buf[i])).to_string();
+        let stderr = partial_line(&std::mem::take(&mut self.runner_stderr_buf[i])).to_string();
+        let flushed = !stdout.is_empty() || !stderr.is_empty();
+
+        if !stdout.is_empty() {
+            self.push_line(i, IO::Stdout(stdout));
+        }
+        if !stderr.is_empty() {
+            self.push_line(i, IO::Stderr(stderr));
+        }
+
+        flushed
+    }
+
+    /// Drop runner `i`'s logs, including any partial lines.
+    /// The scroll state needs rebuilding afterwards.
+    fn clear_logs(&mut self, i: usize) {
+        self.scroll_state.evict(i, self.logs[i].len());
+        for (.., seq) in self.logs[i].drain(..) {
+            self.repeats.remove(&seq);
         }
-        fn to_row_io<'a>(name: &'a str, io: &'a IO) -> iced::Element<'a, Message> {
-            let (glyph, line) = match io {
-                IO::Stdout(line) => (GLYPH_STDOUT, line),
-                IO::Stderr(line) => (GLYPH_STDERR, line),
+        self.runner_stdout_buf[i].clear();
+        self.runner_stderr_buf[i].clear();
+    }
+
+    /// Mark runners that have been running without output for longer than
+    /// their `stall_timeout` as of `now`, notifying if they ask to be
+    fn check_stalls(&mut self, now: SystemTime) {
+        for (i, runner) in self.runners.iter().enumerate() {
+            let stalled = match (runner.stall_timeout, runner.start_time()) {
+                (Some(timeout), Some(start_time)) if !runner.is_paused() => {
+                    // Output from an earlier run doesn't count for this one
+                    let since = self.last_output[i]
+                        .filter(|&time| time >= start_time)
+                        .unwrap_or(start_time);
+                    now.duration_since(since).unwrap_or_default() >= timeout
+                }
+                _ => false,
             };
-            to_row(name, glyph, line)
+            if stalled && !self.stalled[i] && runner.notify_on_stall {
+                crate::notify::send(
+                    format!("{} stalled", runner.name),
+                    format!(
+                        "No output for {}",
+                        runner::format_duration(runner.stall_timeout.unwrap_or_default())
+                    ),
+                );
+            }
+            self.stalled[i] = stalled;
+        }
+    }
+
+    /// Update running runners' CPU and memory use, if it's time to
+    fn sample_usage(&mut self) {
+        if !self.runners.iter().any(Runner::is_running) {
+            return;
+        }
+        let pids = self.runners.iter().map(Runner::pid).collect::<Vec<_>>();
+        if let Some(usages) = self.usage_monitor.sample(&pids) {
+            for (runner, usage) in self.runners.iter_mut().zip(usages) {
+                runner.usage = usage;
+            }
+        }
+    }
+
+    /// Timestamp for a line runner `i` logged at `time`, in the current mode
+    fn timestamp(&self, i: usize, time: SystemTime) -> Option<String> {
+        match self.timestamp_mode {
+            TimestampMode::Off => None,
+            TimestampMode::Absolute => Some(
+                chrono::DateTime::<chrono::Local>::from(time)
+                    .format("%H:%M:%S%.3f")
+                    .to_string(),
+            ),
+            TimestampMode::Relative => {
+                // Latest run started at or before this line
+                let run_starts = &self.run_starts[i];
+                let run = run_starts.partition_point(|&start| start <= time);
+                let start = run.checked_sub(1).map_or(time, |run| run_starts[run]);
+                let offset = time.duration_since(start).unwrap_or_default();
+                Some(format!("+{:.3}s", offset.as_secs_f64()))
+            }
+        }
+    }
+
+    fn dependencies_ready(&self, i: usize) -> bool {
+        self.dependencies[i]
+            .iter()
+            .all(|&j| self.runners[j].is_ready())
+    }
+
+    /// Run any waiting runners whose dependencies have become ready
+    fn run_waiting(&mut self) -> iced::Task<Message> {
+        iced::Task::batch(
+            (0..self.runners.len())
+                .filter(|&i| self.runners[i].waiting && self.dependencies_ready(i))
+                .map(|i| {
+                    let id = self.runner_ids[i];
+                    iced::Task::done(Message::RunnerTask(id, runner::Message::ScriptRun))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Unique runner tags, in order of first appearance
+    fn tags(&self) -> Vec<&str> {
+        let mut tags = Vec::<&str>::new();
+        for tag in self.runners.iter().flat_map(|r| &r.tags) {
+            if !tags.contains(&tag.as_str()) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Whether runner `i` passes the current tag filter
+    fn runner_matches_filter(&self, i: usize) -> bool {
+        match &self.tag_filter {
+            Some(tag) => self.runners[i].tags.contains(tag),
+            None => true,
+        }
+    }
+
+    /// Whether runner `i` is listed, passing the tag filter and the
+    /// runner search, ignoring case
+    fn runner_listed(&self, i: usize) -> bool {
+        let query = self.runner_query.to_lowercase();
+        let runner = &self.runners[i];
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+        self.runner_matches_filter(i)
+            && (matches(&runner.name)
+                || runner.description.as_deref().is_some_and(matches)
+                || runner.tags.iter().any(|tag| matches(tag)))
+    }
+
+    /// Indices of the listed runners, in the order they're listed.
+    /// Ties keep config order.
+    fn listed_runners(&self) -> Vec<usize> {
+        let mut idxs = (0..self.runners.len())
+            .filter(|&i| self.runner_listed(i))
+            .collect::<Vec<_>>();
+        match self.runner_sort {
+            RunnerSort::Config => (),
+            RunnerSort::Name => idxs.sort_by_key(|&i| self.runners[i].name.to_lowercase()),
+            RunnerSort::Status => idxs.sort_by_key(|&i| {
+                let runner = &self.runners[i];
+                if runner.is_running() {
+                    0
+                } else if runner.failed() {
+                    1
+                } else {
+                    2
+                }
+            }),
+        }
+        idxs
+    }
+
+    /// Whether runner `i`'s logs are shown in the log pane
+    fn runner_logs_shown(&self, i: usize) -> bool {
+        match self.solo {
+            Some(solo) => i == solo,
+            None => self.runners[i].show_logs && self.runner_matches_filter(i),
+        }
+    }
+
+    /// The configured glyph for `io`'s stream
+    fn glyph(&self, io: &IO) -> &str {
+        match io {
+            IO::Stdout(_) => &self.stdout_glyph,
+            IO::Stderr(_) => &self.stderr_glyph,
+        }
+    }
+
+    /// Plain text for log lines, one per row as shown in the pane
+    fn log_text(&self, logs: &[scroll_state::ScrollStateLog]) -> String {
+        let mut text = String::new();
+        for ssl in logs {
+            let (time, io, _, seq) = &self.logs[ssl.runner_idx][ssl.log_pos];
+            if let Some(timestamp) = self.timestamp(ssl.runner_idx, *time) {
+                text.push_str(&timestamp);
+                text.push(' ');
+            }
+            text.push_str(&format!(
+                "{}{} {}{}\n",
+                self.runners[ssl.runner_idx].name,
+                self.glyph(io),
+                io.line(),
+                self.repeat_suffix(*seq)
+            ));
+        }
+        text
+    }
+
+    /// How many times line `seq` was logged in a row, e.g. ` \u{d7}3`,
+    /// or nothing if it was only logged once
+    fn repeat_suffix(&self, seq: u64) -> String {
+        match self.repeats.get(&seq) {
+            Some(n) => format!(" \u{d7}{n}"),
+            None => String::new(),
+        }
+    }
+
+    /// Write log lines to `path` in `format`, one log line per line
+    fn export_logs(
+        &self,
+        path: &std::path::Path,
+        format: ExportFormat,
+        logs: &[scroll_state::ScrollStateLog],
+    ) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for ssl in logs {
+            let (time, io, _, seq) = &self.logs[ssl.runner_idx][ssl.log_pos];
+            let ts = chrono::DateTime::<chrono::Local>::from(*time)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+            let runner = &self.runners[ssl.runner_idx].name;
+            match format {
+                ExportFormat::Text => {
+                    let repeats = self.repeat_suffix(*seq);
+                    writeln!(w, "{ts} {runner} {} {}{repeats}", self.glyph(io), io.line())?;
+                }
+                ExportFormat::JsonLines => {
+                    let stream = match io {
+                        IO::Stdout(_) => "stdout",
+                        IO::Stderr(_) => "stderr",
+                    };
+                    let mut json = serde_json::json!({
+                        "ts": ts,
+                        "runner": runner,
+                        "stream": stream,
+                        "line": io.line(),
+                    });
+                    if let Some(repeats) = self.repeats.get(seq) {
+                        json["repeats"] = (*repeats).into();
+                    }
+                    writeln!(w, "{json}")?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    /// Every runner's whole log history, in order
+    fn all_logs(&self) -> Vec<scroll_state::ScrollStateLog> {
+        let runner_idxs = (0..self.runners.len()).collect::<Vec<_>>();
+        scroll_state::merge(&self.logs, &runner_idxs, &Default::default())
+    }
+
+    fn report_export(path: &std::path::Path, n_lines: usize, result: std::io::Result<()>) {
+        match result {
+            Ok(()) => tracing::info!("wrote {n_lines} lines to {}", path.display()),
+            Err(err) => tracing::error!("error writing {}: {err}", path.display()),
+        }
+    }
+
+    /// Filter the log pane by the search box. If its query isn't usable,
+    /// the previous pattern stays, but the stream selection still applies.
+    fn apply_log_search(&mut self) -> iced::Task<Message> {
+        let pattern = match self.log_search.pattern() {
+            Ok(pattern) => {
+                self.log_search.error = None;
+                pattern
+            }
+            Err(err) => {
+                self.log_search.error = Some(err.to_string());
+                self.scroll_state.filter().pattern.clone()
+            }
+        };
+        let filter = scroll_state::LogFilter {
+            pattern,
+            streams: self.log_search.streams,
+        };
+        self.scroll_state
+            .set_filter(filter)
+            .map(Message::ScrollState)
+    }
+
+    /// Rebuild the log pane's runner selection
+    fn update_runner_idxs(&mut self) -> iced::Task<Message> {
+        let runner_idxs = (0..self.runners.len())
+            .filter(|&i| self.runner_logs_shown(i))
+            .collect::<Vec<_>>();
+        self.scroll_state
+            .set_runner_idxs(runner_idxs.into_iter())
+            .map(Message::ScrollState)
+    }
+
+    /// The color of runner `i`'s name in the logs: its own, or else one
+    /// from the palette by its position
+    fn runner_color(&self, i: usize) -> iced::Color {
+        self.runners[i]
+            .color
+            .unwrap_or(RUNNER_PALETTE[i % RUNNER_PALETTE.len()])
+    }
+
+    pub fn view<'a>(&'a self) -> iced::Element<'a, Message> {
+        let font = self.mono_font;
+        let runner_search = widget::text_input("find runners", &self.runner_query)
+            .on_input(Message::SetRunnerQuery)
+            .width(iced::Length::Fixed(200.0));
+        let sort_icon = match self.runner_sort {
+            RunnerSort::Config => crate::icon::Nerd::SortNumericAscending,
+            RunnerSort::Name => crate::icon::Nerd::SortAlphabeticalAscending,
+            RunnerSort::Status => crate::icon::Nerd::SortBoolAscending,
+        };
+        let sort_button = widget::button(crate::icon::to_text(sort_icon))
+            .on_press(Message::SetRunnerSort(self.runner_sort.next()))
+            .style(if self.runner_sort == RunnerSort::Config {
+                widget::button::secondary
+            } else {
+                widget::button::success
+            });
+        let runner_search = widget::row![runner_search, sort_button].spacing(5);
+        let runners = Column::from_iter(std::iter::once(runner_search.into()).chain(
+            self.listed_runners().into_iter().map(|i| {
+                self.runners[i]
+                    .view(self.solo == Some(i), self.stalled[i], &self.output_rates[i])
+                    .map(move |msg| Message::Runner(i, msg))
+            }),
+        ))
+        .spacing(10);
+
+        fn to_row<'a>(
+            timestamp: Option<String>,
+            (name, name_color): (&'a str, iced::Color),
+            glyph: &'a str,
+            line: iced::Element<'a, Message>,
+            tint: Option<iced::Color>,
+            gutter: Option<String>,
+            font: iced::Font,
+        ) -> iced::Element<'a, Message> {
+            let gutter = gutter.map(|gutter| {
+                widget::text(format!("{gutter} "))
+                    .font(font)
+                    .style(widget::text::secondary)
+            });
+            let timestamp = timestamp.map(|timestamp| {
+                widget::text(format!("{timestamp} "))
+                    .font(font)
+                    .style(widget::text::secondary)
+            });
+            widget::row![]
+                .push_maybe(gutter)
+                .push_maybe(timestamp)
+                .push(widget::text(name).font(font).color(name_color))
+                .push(widget::text(glyph).font(font).color_maybe(tint))
+                .push(widget::text(" ").font(font))
+                .push(line)
+                .into()
+        }
+        fn to_row_io<'a>(
+            timestamp: Option<String>,
+            name: (&'a str, iced::Color),
+            io: &'a IO,
+            spans: &[ansi::Span],
+            levels: &[(String, iced::Color)],
+            gutter: Option<String>,
+            style: LineStyle<'a>,
+        ) -> iced::Element<'a, Message> {
+            let line = io.line();
+            let tint = style.tint(io, level_color(levels, line));
+            to_row(
+                timestamp,
+                name,
+                style.glyph(io),
+                styled_line(line.into(), spans, tint, style),
+                tint,
+                gutter,
+                style.font,
+            )
+        }
+        // Partial lines haven't been parsed yet, so parse them as drawn
+        fn to_row_partial<'a>(
+            runner: &'a Runner,
+            color: iced::Color,
+            io: &IO,
+            levels: &[(String, iced::Color)],
+            gutter: Option<String>,
+            style: LineStyle<'a>,
+        ) -> iced::Element<'a, Message> {
+            let (line, mut spans) = ansi::parse(io.line());
+            let line = ansi::expand_tabs(&line, &mut spans, style.tab_width);
+            if runner.strip_ansi {
+                spans.clear();
+            }
+            let tint = style.tint(io, level_color(levels, &line));
+            let line = styled_line(line.into(), &spans, tint, style);
+            to_row(
+                None,
+                (&runner.name, color),
+                style.glyph(io),
+                line,
+                tint,
+                gutter,
+                style.font,
+            )
         }
 
         let mut scroll_contents = Vec::<iced::Element<_>>::new();
@@ -73,26 +1220,75 @@ impl App {
         scroll_contents.push(
             widget::Space::with_height(iced::Length::Fixed(self.scroll_state.space_before)).into(),
         );
+        // Line numbers count through the merged timeline, right-aligned to
+        // the widest. Rows are drawn consecutively after space_before, so
+        // the first row's number is the number of lines it stands in for.
+        let gutter_width = self.scroll_state.total_lines.max(1).to_string().len();
+        let gutter = |n: Option<usize>| {
+            self.line_numbers.then(|| match n {
+                Some(n) => format!("{n:>gutter_width$}"),
+                None => " ".repeat(gutter_width),
+            })
+        };
+        let line_style = LineStyle {
+            font: self.mono_font,
+            wrap: self.wrap_lines,
+            stdout_glyph: &self.stdout_glyph,
+            stderr_glyph: &self.stderr_glyph,
+            stderr_color: self.stderr_color,
+            tab_width: self.tab_width,
+        };
         // visible text
-        scroll_contents.extend(self.scroll_state.logs.iter().map(|ssl| {
-            to_row_io(
-                &self.runners[ssl.runner_idx].name,
-                &self.logs[ssl.runner_idx][ssl.log_pos].1,
-            )
+        scroll_contents.extend(self.scroll_state.logs.iter().enumerate().map(|(n, ssl)| {
+            let (time, io, spans, seq) = &self.logs[ssl.runner_idx][ssl.log_pos];
+            let row = to_row_io(
+                self.timestamp(ssl.runner_idx, *time),
+                (
+                    &self.runners[ssl.runner_idx].name,
+                    self.runner_color(ssl.runner_idx),
+                ),
+                io,
+                spans,
+                &self.level_colors,
+                gutter(Some(self.scroll_state.first_line + n + 1)),
+                line_style,
+            );
+            match self.repeats.get(seq) {
+                Some(repeats) => widget::row![
+                    row,
+                    widget::text(format!(" \u{d7}{repeats}"))
+                        .font(font)
+                        .style(widget::text::secondary)
+                ]
+                .into(),
+                None => row,
+            }
         }));
         // culled lines after
         scroll_contents.push(
             widget::Space::with_height(iced::Length::Fixed(self.scroll_state.space_after)).into(),
         );
         // most recent lines
+        let filter = self.scroll_state.filter();
         for i in 0..self.runners.len() {
-            if !self.runner_stdout_buf[i].is_empty() && self.runners[i].show_logs {
-                let stdout = &self.runner_stdout_buf[i];
-                scroll_contents.push(to_row(&self.runners[i].name, GLYPH_STDOUT, stdout));
+            if !self.runner_logs_shown(i) {
+                continue;
             }
-            if !self.runner_stderr_buf[i].is_empty() && self.runners[i].show_logs {
-                let stderr = &self.runner_stderr_buf[i];
-                scroll_contents.push(to_row(&self.runners[i].name, GLYPH_STDERR, stderr));
+            let partials = [
+                IO::Stdout(partial_line(&self.runner_stdout_buf[i]).to_string()),
+                IO::Stderr(partial_line(&self.runner_stderr_buf[i]).to_string()),
+            ];
+            for io in partials {
+                if !io.line().is_empty() && filter.matches(&io) {
+                    scroll_contents.push(to_row_partial(
+                        &self.runners[i],
+                        self.runner_color(i),
+                        &io,
+                        &self.level_colors,
+                        gutter(None),
+                        line_style,
+                    ));
+                }
             }
         }
 
@@ -100,6 +1296,14 @@ impl App {
             widget::scrollable(Column::from_vec(scroll_contents))
                 .width(iced::Length::Fill)
                 .height(iced::Length::Fill)
+                .direction(if self.wrap_lines {
+                    widget::scrollable::Direction::Vertical(Default::default())
+                } else {
+                    widget::scrollable::Direction::Both {
+                        vertical: Default::default(),
+                        horizontal: Default::default(),
+                    }
+                })
                 .on_scroll(|v| Message::ScrollState(scroll_state::Message::OnScroll(v)))
                 .id(self.scroll_state.id.clone())
                 .anchor_y(self.scroll_state.anchor_y),
@@ -116,65 +1320,428 @@ impl App {
         .height(iced::Length::Fill)
         .padding(5);
 
-        Row::from_iter([runners.into(), logs.into()])
-            .padding(10)
-            .spacing(10)
+        let toggle = |label, on: bool, message: fn(bool) -> Message| {
+            widget::button(widget::text(label).font(font))
+                .on_press(message(!on))
+                .style(if on {
+                    widget::button::success
+                } else {
+                    widget::button::secondary
+                })
+        };
+        let search_status = match &self.log_search.error {
+            Some(err) => Some(widget::text(err).style(widget::text::danger)),
+            None => filter.is_active().then(|| {
+                widget::text(format!("{} matches", self.scroll_state.total_lines))
+                    .style(widget::text::secondary)
+            }),
+        };
+        let search = widget::row![
+            widget::text_input("search logs", &self.log_search.query)
+                .on_input(Message::SetLogFilter)
+                .font(font),
+            toggle(".*", self.log_search.regex, Message::SetLogFilterRegex),
+            toggle(
+                "Aa",
+                self.log_search.case_insensitive,
+                Message::SetLogFilterCaseInsensitive
+            ),
+        ]
+        .extend(
+            [
+                ("all", scroll_state::Streams::Both),
+                (self.stdout_glyph.as_str(), scroll_state::Streams::Stdout),
+                (self.stderr_glyph.as_str(), scroll_state::Streams::Stderr),
+            ]
+            .map(|(label, streams)| {
+                widget::button(widget::text(label).font(font))
+                    .on_press(Message::SetLogFilterStreams(streams))
+                    .style(if self.log_search.streams == streams {
+                        widget::button::success
+                    } else {
+                        widget::button::secondary
+                    })
+                    .into()
+            }),
+        )
+        .push_maybe(search_status)
+        .align_y(iced::Alignment::Center)
+        .spacing(5);
+        // Shown while scrolled up reading history, with the number of new
+        // lines since
+        let jump_button =
+            (self.scroll_state.anchor_y == widget::scrollable::Anchor::Start).then(|| {
+                let unread = self.scroll_state.unread();
+                let label = match unread {
+                    0 => "latest".to_string(),
+                    n => format!("{n} new"),
+                };
+                widget::container(
+                    widget::button(
+                        widget::row![
+                            crate::icon::to_text(crate::icon::Nerd::ArrowCollapseDown),
+                            widget::text(label),
+                        ]
+                        .spacing(5),
+                    )
+                    .on_press(Message::ScrollState(scroll_state::Message::JumpToEnd))
+                    .style(if unread > 0 {
+                        widget::button::primary
+                    } else {
+                        widget::button::secondary
+                    }),
+                )
+                .width(iced::Length::Fill)
+                .height(iced::Length::Fill)
+                .align_x(iced::alignment::Horizontal::Right)
+                .align_y(iced::alignment::Vertical::Bottom)
+                .padding(20)
+            });
+        let logs = widget::Stack::new().push(logs).push_maybe(jump_button);
+
+        let logs = widget::column![search, logs].spacing(5);
+
+        // Portions of a hundred, as FillPortion only takes integers
+        let runners_portion = (self.split * 100.0).round() as u16;
+        let main: iced::Element<'a, Message> = if self.runners_collapsed {
+            logs.into()
+        } else {
+            let divider = widget::mouse_area(
+                widget::container(widget::Space::new(
+                    iced::Length::Fixed(4.0),
+                    iced::Length::Fill,
+                ))
+                .style(|theme: &iced::Theme| {
+                    let color = if self.split_dragging {
+                        theme.extended_palette().primary.base.color
+                    } else {
+                        theme.extended_palette().background.strong.color
+                    };
+                    widget::container::background(color)
+                }),
+            )
+            .on_press(Message::SplitDragStarted)
+            .interaction(iced::mouse::Interaction::ResizingHorizontally);
+            widget::row![
+                widget::container(runners)
+                    .width(iced::Length::FillPortion(runners_portion))
+                    .clip(true),
+                divider,
+                widget::container(logs).width(iced::Length::FillPortion(100 - runners_portion)),
+            ]
+            .spacing(5)
             .into()
-    }
+        };
 
-    pub fn update(&mut self, message: Message) -> iced::Task<Message> {
-        match message {
-            Message::Runner(i, message) => {
-                let task = self.runners[i].update(message.clone());
-                let mut task = task.map(move |msg| Message::Runner(i, msg));
+        let run_all_button = widget::button(crate::icon::to_text(crate::icon::Nerd::PlayCircle))
+            .on_press(Message::RunAll)
+            .style(widget::button::secondary);
 
-                match message {
-                    runner::Message::Stdout(ref s) => {
-                        let mut s: &str = s;
-                        // read until '\n'
-                        while !s.is_empty() {
-                            match s.find('\n') {
-                                Some(n) => {
-                                    self.runner_stdout_buf[i].push_str(&s[..n]);
-                                    let line = std::mem::take(&mut self.runner_stdout_buf[i]);
-                                    self.logs[i].push((SystemTime::now(), IO::Stdout(line)));
-                                    s = &s[n + 1..];
-                                }
-                                None => {
-                                    self.runner_stdout_buf[i].push_str(s);
-                                    break;
-                                }
-                            };
-                        }
+        let (_, failed) = self.status_counts();
+        let run_failed_button = widget::button(crate::icon::to_text(crate::icon::Nerd::Restart))
+            .on_press_maybe((failed > 0).then_some(Message::RunFailed))
+            .style(widget::button::secondary);
 
-                        if self.runners[i].show_logs {
-                            let scroll_task = self
-                                .scroll_state
-                                .update_logs(&self.logs)
+        let stop_all: iced::Element<'a, Message> = if self.confirm_stop_all {
+            widget::row![
+                widget::text("Stop all?"),
+                widget::button(crate::icon::to_text(crate::icon::Nerd::Check))
+                    .on_press(Message::ConfirmStopAll(true))
+                    .style(widget::button::danger),
+                widget::button(crate::icon::to_text(crate::icon::Nerd::Close))
+                    .on_press(Message::ConfirmStopAll(false))
+                    .style(widget::button::secondary),
+            ]
+            .spacing(5)
+            .align_y(iced::Alignment::Center)
+            .into()
+        } else {
+            widget::button(crate::icon::to_text(crate::icon::Nerd::StopCircle))
+                .on_press(Message::StopAll)
+                .style(widget::button::secondary)
+                .into()
+        };
+
+        let clear_button = widget::button(crate::icon::to_text(crate::icon::Nerd::Broom))
+            .on_press(Message::ClearLogs)
+            .style(widget::button::secondary);
+
+        let copy_button = widget::button(crate::icon::to_text(crate::icon::Nerd::ContentCopy))
+            .on_press(Message::CopyLogs)
+            .style(widget::button::secondary);
+
+        let export_button = |label, message| {
+            widget::button(
+                widget::row![
+                    crate::icon::to_text(crate::icon::Nerd::FileExport),
+                    widget::text(label)
+                ]
+                .spacing(5),
+            )
+            .on_press(message)
+            .style(widget::button::secondary)
+        };
+        let export_path = export_path(self.export_format);
+        let export_all_button = export_button("all", Message::ExportLogs(export_path.clone()));
+        let export_shown_button = export_button("shown", Message::ExportFilteredLogs(export_path));
+        let export_format_button =
+            widget::button(widget::text(self.export_format.extension()).font(font))
+                .on_press(Message::SetExportFormat(match self.export_format {
+                    ExportFormat::Text => ExportFormat::JsonLines,
+                    ExportFormat::JsonLines => ExportFormat::Text,
+                }))
+                .style(widget::button::secondary);
+
+        let timestamp_button = match self.timestamp_mode {
+            TimestampMode::Off => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::ClockOutline))
+                    .style(widget::button::secondary)
+            }
+            TimestampMode::Absolute => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::Clock))
+                    .style(widget::button::success)
+            }
+            TimestampMode::Relative => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::ClockStart))
+                    .style(widget::button::success)
+            }
+        }
+        .on_press(Message::SetTimestampMode(self.timestamp_mode.next()));
+
+        let line_numbers_button =
+            widget::button(crate::icon::to_text(crate::icon::Nerd::FormatListNumbered))
+                .on_press(Message::SetLineNumbers(!self.line_numbers))
+                .style(if self.line_numbers {
+                    widget::button::success
+                } else {
+                    widget::button::secondary
+                });
+        let follow_button = match self.scroll_state.follow() {
+            scroll_state::Follow::Auto => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::ArrowDown))
+                    .style(widget::button::secondary)
+            }
+            scroll_state::Follow::Always => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::Pin))
+                    .style(widget::button::success)
+            }
+            scroll_state::Follow::Never => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::PinOff))
+                    .style(widget::button::success)
+            }
+        }
+        .on_press(Message::ScrollState(scroll_state::Message::SetFollow(
+            self.scroll_state.follow().next(),
+        )));
+        let collapse_button = widget::button(crate::icon::to_text(crate::icon::Nerd::DockLeft))
+            .on_press(Message::SetRunnersCollapsed(!self.runners_collapsed))
+            .style(if self.runners_collapsed {
+                widget::button::secondary
+            } else {
+                widget::button::success
+            });
+        let wrap_button = if self.wrap_lines {
+            widget::button(crate::icon::to_text(crate::icon::Nerd::Wrap))
+                .style(widget::button::success)
+        } else {
+            widget::button(crate::icon::to_text(crate::icon::Nerd::WrapDisabled))
+                .style(widget::button::secondary)
+        }
+        .on_press(Message::SetWrapLines(!self.wrap_lines));
+
+        // Cycles through every built-in theme
+        let next_theme = iced::Theme::ALL
+            .iter()
+            .cycle()
+            .skip_while(|theme| **theme != self.theme)
+            .nth(1)
+            .unwrap_or(&iced::Theme::ALL[0]);
+        let theme_button = widget::button(crate::icon::to_text(crate::icon::Nerd::ThemeLightDark))
+            .on_press(Message::SetTheme(next_theme.clone()))
+            .style(widget::button::secondary);
+
+        let tag_button = |label: &'a str, tag: Option<&str>| {
+            let selected = self.tag_filter.as_deref() == tag;
+            widget::button(widget::text(label))
+                .on_press(Message::SetTagFilter(tag.map(str::to_string)))
+                .style(if selected {
+                    widget::button::success
+                } else {
+                    widget::button::secondary
+                })
+        };
+        let tags = self.tags();
+        let tag_buttons = (!tags.is_empty())
+            .then(|| {
+                std::iter::once(tag_button("All", None).into())
+                    .chain(tags.iter().map(|tag| tag_button(tag, Some(tag)).into()))
+            })
+            .into_iter()
+            .flatten();
+
+        let toolbar = Row::with_children(
+            [
+                collapse_button.into(),
+                run_all_button.into(),
+                run_failed_button.into(),
+                stop_all,
+                clear_button.into(),
+                copy_button.into(),
+                export_all_button.into(),
+                export_shown_button.into(),
+                export_format_button.into(),
+                timestamp_button.into(),
+                line_numbers_button.into(),
+                wrap_button.into(),
+                follow_button.into(),
+                theme_button.into(),
+            ]
+            .into_iter()
+            .chain(tag_buttons),
+        )
+        .spacing(5);
+
+        let (running, failed) = self.status_counts();
+        let lines = self.logs.iter().map(VecDeque::len).sum::<usize>();
+        let status_bar = widget::text(if self.closing.is_some() {
+            format!("Stopping {running} runners before exiting\u{2026}")
+        } else {
+            format!("{running} running, {failed} failed, {lines} log lines")
+        })
+        .size(12)
+        .style(if failed > 0 {
+            widget::text::danger
+        } else {
+            widget::text::secondary
+        });
+
+        let content = widget::column![
+            toolbar,
+            widget::container(main).height(iced::Fill),
+            status_bar
+        ]
+        .padding(10)
+        .spacing(10);
+
+        let confirm_kill = self.confirm_kill.map(|(i, _)| {
+            let prompt = widget::container(
+                widget::column![
+                    widget::text(format!("Stop {}?", self.runners[i].name)),
+                    widget::row![
+                        widget::button("Stop")
+                            .on_press(Message::ConfirmKill(true))
+                            .style(widget::button::danger),
+                        widget::button("Cancel")
+                            .on_press(Message::ConfirmKill(false))
+                            .style(widget::button::secondary),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(10),
+            )
+            .padding(20)
+            .style(widget::container::rounded_box);
+
+            // Clicking outside the prompt cancels it
+            widget::opaque(
+                widget::mouse_area(widget::center(widget::opaque(prompt)).style(|_theme| {
+                    widget::container::Style::default()
+                        .background(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.5))
+                }))
+                .on_press(Message::ConfirmKill(false)),
+            )
+        });
+
+        widget::Stack::new()
+            .push(content)
+            .push_maybe(confirm_kill)
+            .into()
+    }
+
+    pub fn update(&mut self, message: Message) -> iced::Task<Message> {
+        match message {
+            Message::Runner(
+                i,
+                message @ (runner::Message::ScriptRun | runner::Message::ScriptRunManual),
+            ) if !self.runners[i].is_running() && !self.dependencies_ready(i) => {
+                tracing::debug!(runner = %self.runners[i].name, "waiting for dependencies");
+                if let runner::Message::ScriptRunManual = message {
+                    self.runners[i].reset_run_counts();
+                }
+                self.runners[i].waiting = true;
+                iced::Task::none()
+            }
+
+            Message::Runner(i, runner::Message::ConfirmKill { start_time }) => {
+                self.confirm_kill = Some((i, start_time));
+                iced::Task::none()
+            }
+
+            Message::RunnerTask(id, message) => match self.runner_index(id) {
+                Some(i) => self.update(Message::Runner(i, message)),
+                // The runner has since been removed
+                None => iced::Task::none(),
+            },
+
+            Message::Runner(i, message) => {
+                if let runner::Message::ScriptRun | runner::Message::ScriptRunManual = message {
+                    self.runners[i].waiting = false;
+                }
+
+                let task = self.runners[i].update(message.clone());
+                let id = self.runner_ids[i];
+                let mut task = task.map(move |msg| Message::RunnerTask(id, msg));
+
+                // Runs start inside Runner::update, so note new ones here
+                if let Some(start_time) = self.runners[i].start_time()
+                    && self.run_starts[i].last() != Some(&start_time)
+                {
+                    self.run_starts[i].push(start_time);
+                }
+
+                if let runner::Message::Stdout(_) | runner::Message::Stderr(_) = message {
+                    self.last_output[i] = Some(SystemTime::now());
+                    self.stalled[i] = false;
+                }
+
+                match message {
+                    runner::Message::Stdout(ref s) => {
+                        let mut became_ready = false;
+                        let lines = split_lines(
+                            &mut self.runner_stdout_buf[i],
+                            s,
+                            self.runners[i].max_line_len,
+                        );
+                        self.output_rates[i].record(lines.len() as u32, std::time::Instant::now());
+                        for line in lines {
+                            became_ready |= self.runners[i].check_ready_line(&line);
+                            self.push_line(i, IO::Stdout(line));
+                        }
+                        if became_ready {
+                            task = iced::Task::batch([task, self.run_waiting()]);
+                        }
+
+                        if self.runner_logs_shown(i) {
+                            let scroll_task = self
+                                .scroll_state
+                                .update_logs(&self.logs)
                                 .map(Message::ScrollState);
                             task = iced::Task::batch([task, scroll_task]);
                         }
                     }
 
                     runner::Message::Stderr(ref s) => {
-                        let mut s: &str = s;
-                        // read until '\n'
-                        while !s.is_empty() {
-                            match s.find('\n') {
-                                Some(n) => {
-                                    self.runner_stderr_buf[i].push_str(&s[..n]);
-                                    let line = std::mem::take(&mut self.runner_stderr_buf[i]);
-                                    self.logs[i].push((SystemTime::now(), IO::Stderr(line)));
-                                    s = &s[n + 1..];
-                                }
-                                None => {
-                                    self.runner_stderr_buf[i].push_str(s);
-                                    break;
-                                }
-                            };
+                        let lines = split_lines(
+                            &mut self.runner_stderr_buf[i],
+                            s,
+                            self.runners[i].max_line_len,
+                        );
+                        self.output_rates[i].record(lines.len() as u32, std::time::Instant::now());
+                        for line in lines {
+                            self.push_line(i, IO::Stderr(line));
                         }
 
-                        if self.runners[i].show_logs {
+                        if self.runner_logs_shown(i) {
                             let scroll_task = self
                                 .scroll_state
                                 .update_logs(&self.logs)
@@ -184,39 +1751,1459 @@ impl App {
                     }
 
                     runner::Message::SetShowLogs(_) => {
-                        let scroll_task = self
-                            .scroll_state
-                            .set_runner_idxs(
-                                self.runners
-                                    .iter()
-                                    .enumerate()
-                                    .filter(|(_, r)| r.show_logs)
-                                    .map(|(i, _)| i),
-                            )
-                            .map(Message::ScrollState);
+                        let scroll_task = self.update_runner_idxs();
+                        task = iced::Task::batch([task, scroll_task]);
+                    }
 
+                    runner::Message::SetSolo(solo) => {
+                        self.solo = solo.then_some(i);
+                        let scroll_task = self.update_runner_idxs();
                         task = iced::Task::batch([task, scroll_task]);
                     }
+
+                    runner::Message::ClearLogs => {
+                        self.clear_logs(i);
+                        let scroll_task = self.update_runner_idxs();
+                        task = iced::Task::batch([task, scroll_task]);
+                    }
+
+                    runner::Message::ScriptRun | runner::Message::ScriptRunManual => {
+                        task = iced::Task::batch([task, self.run_waiting()]);
+                    }
+
+                    runner::Message::ScriptComplete {
+                        status,
+                        signal,
+                        start_time,
+                        end_time,
+                    } => {
+                        task = iced::Task::batch([task, self.run_waiting()]);
+
+                        let runner = &self.runners[i];
+                        if runner.notify_on_complete || (status != 0 && self.notify_on_failure) {
+                            let runtime = end_time.duration_since(start_time).unwrap_or_default();
+                            let outcome = if runner.timed_out() {
+                                "timed out"
+                            } else if status == 0 {
+                                "finished"
+                            } else {
+                                "failed"
+                            };
+                            let exit = match signal {
+                                Some(signal) => {
+                                    let (reason, name) = runner::describe_signal(signal);
+                                    format!("{reason} ({name})")
+                                }
+                                None => format!("Exited with status {status}"),
+                            };
+                            crate::notify::send(
+                                format!("{} {outcome}", runner.name),
+                                format!("{exit} after {}", runner::format_duration(runtime)),
+                            );
+                        }
+
+                        // Keep output that never saw a final newline
+                        if self.flush_partial_lines(i) && self.runner_logs_shown(i) {
+                            let scroll_task = self
+                                .scroll_state
+                                .update_logs(&self.logs)
+                                .map(Message::ScrollState);
+                            task = iced::Task::batch([task, scroll_task]);
+                        }
+
+                        if let Some(id) = self.closing
+                            && !self.runners.iter().any(Runner::is_running)
+                        {
+                            task = iced::Task::batch([task, self.close(id)]);
+                        }
+                    }
                     _ => (),
                 }
 
                 task
             }
 
+            #[cfg(feature = "api")]
+            Message::Api(call) => self.answer(call),
+
             Message::ScrollState(message) => self
                 .scroll_state
                 .update(message, &self.logs)
                 .map(Message::ScrollState),
+
+            Message::SetTagFilter(tag) => {
+                self.tag_filter = tag;
+                self.update_runner_idxs()
+            }
+
+            Message::SetRunnerQuery(query) => {
+                self.runner_query = query;
+                iced::Task::none()
+            }
+
+            Message::SetRunnerSort(runner_sort) => {
+                self.runner_sort = runner_sort;
+                iced::Task::none()
+            }
+
+            Message::CopyLogs => {
+                let logs = self.scroll_state.merged(&self.logs);
+                iced::clipboard::write(self.log_text(&logs))
+            }
+
+            Message::ExportLogs(path) => {
+                let logs = self.all_logs();
+                let result = self.export_logs(&path, self.export_format, &logs);
+                App::report_export(&path, logs.len(), result);
+                iced::Task::none()
+            }
+            Message::ExportFilteredLogs(path) => {
+                let logs = self.scroll_state.merged(&self.logs);
+                let result = self.export_logs(&path, self.export_format, &logs);
+                App::report_export(&path, logs.len(), result);
+                iced::Task::none()
+            }
+            Message::SetExportFormat(export_format) => {
+                self.export_format = export_format;
+                iced::Task::none()
+            }
+
+            Message::OpenUrl(url) => {
+                if let Err(err) = open::that_detached(&url) {
+                    tracing::warn!("error opening {url}: {err}");
+                }
+                iced::Task::none()
+            }
+
+            // Runners are stopped first, so their scripts all get the kill
+            // sequence rather than relying on the `run` wrapper noticing
+            // we're gone. Asking again closes straight away.
+            Message::WindowCloseRequested(id) => {
+                if self.closing.is_some() || !self.runners.iter().any(Runner::is_running) {
+                    return self.close(id);
+                }
+                tracing::info!("stopping runners before exiting");
+                self.closing = Some(id);
+                let timeout = self
+                    .runners
+                    .iter()
+                    .filter(|runner| runner.is_running())
+                    .map(|runner| runner.kill_timeout)
+                    .max()
+                    .unwrap_or_default()
+                    + runner::KILL_TIMEOUT_MARGIN;
+                let timed_out = iced::Task::future(async move {
+                    tokio::time::sleep(timeout).await;
+                    Message::CloseTimedOut(id)
+                });
+                iced::Task::batch([self.shut_down(), timed_out])
+            }
+            Message::CloseTimedOut(id) => {
+                tracing::warn!("runners are still stopping, exiting anyway");
+                self.close(id)
+            }
+
+            Message::SetTheme(theme) => {
+                self.theme = theme;
+                iced::Task::none()
+            }
+
+            Message::WindowOpened(position, size) => {
+                self.window_position = position.or(self.window_position);
+                self.update(Message::WindowResized(size))
+            }
+            // Minimizing reports a zero size on some platforms
+            Message::WindowResized(size) if size.width > 0.0 && size.height > 0.0 => {
+                self.window_size = Some(size);
+                self.window_changed()
+            }
+            Message::WindowResized(_) => iced::Task::none(),
+            Message::WindowMoved(position) => {
+                self.window_position = Some(position);
+                self.window_changed()
+            }
+            // Saved as the window changes, not just on closing, so it
+            // survives a crash
+            Message::WindowSettled(n) if n == self.window_changes && self.closing.is_none() => {
+                if let Some(path) = &self.session_path {
+                    crate::session::save(path, &self.session());
+                }
+                iced::Task::none()
+            }
+            Message::WindowSettled(_) => iced::Task::none(),
+
+            Message::SetLogFilter(query) => {
+                self.log_search.query = query;
+                self.apply_log_search()
+            }
+            Message::SetLogFilterRegex(regex) => {
+                self.log_search.regex = regex;
+                self.apply_log_search()
+            }
+            Message::SetLogFilterCaseInsensitive(case_insensitive) => {
+                self.log_search.case_insensitive = case_insensitive;
+                self.apply_log_search()
+            }
+            Message::SetLogFilterStreams(streams) => {
+                self.log_search.streams = streams;
+                self.apply_log_search()
+            }
+
+            Message::SetTimestampMode(timestamp_mode) => {
+                self.timestamp_mode = timestamp_mode;
+                iced::Task::none()
+            }
+
+            Message::SetLineNumbers(line_numbers) => {
+                self.line_numbers = line_numbers;
+                iced::Task::none()
+            }
+
+            // Wrapped lines aren't all one line tall, which the culling
+            // relies on, so every line is drawn while wrapping instead
+            Message::SetWrapLines(wrap_lines) => {
+                self.wrap_lines = wrap_lines;
+                self.scroll_state
+                    .set_virtualized(!wrap_lines)
+                    .map(Message::ScrollState)
+            }
+
+            // Nothing new starts while runners are being stopped to exit
+            Message::WatchTriggered(_) | Message::ScheduleDue(..) if self.closing.is_some() => {
+                iced::Task::none()
+            }
+            Message::WatchTriggered(id) => {
+                let Some(i) = self.runner_index(id) else {
+                    return iced::Task::none();
+                };
+                let runner = &self.runners[i];
+                let message = if runner.is_running() {
+                    runner::Message::ScriptRestart
+                } else if runner.watch_always && runner.can_run() {
+                    runner::Message::ScriptRun
+                } else {
+                    return iced::Task::none();
+                };
+                tracing::info!(runner = %runner.name, "watched files changed");
+                self.update(Message::Runner(i, message))
+            }
+
+            Message::ScheduleDue(id, due) => {
+                let Some(i) = self.runner_index(id) else {
+                    return iced::Task::none();
+                };
+                let run = if self.runners[i].can_run() {
+                    self.update(Message::Runner(i, runner::Message::ScriptRun))
+                } else {
+                    tracing::debug!(
+                        runner = %self.runners[i].name,
+                        "skipping scheduled run, as the last is still going"
+                    );
+                    iced::Task::none()
+                };
+                // A schedule that fell behind, e.g. while the machine slept,
+                // picks up from now rather than running for each missed time
+                let next = self.schedule_next(i, due.max(SystemTime::now()));
+                iced::Task::batch([run, next])
+            }
+
+            Message::SetRunnersCollapsed(collapsed) => {
+                self.runners_collapsed = collapsed;
+                iced::Task::none()
+            }
+            Message::SplitDragStarted => {
+                self.split_dragging = true;
+                iced::Task::none()
+            }
+            Message::SplitDragged(x) => {
+                if let Some(size) = self.window_size {
+                    self.split = clamp_split(x / size.width);
+                }
+                iced::Task::none()
+            }
+            Message::SplitDragEnded => {
+                self.split_dragging = false;
+                iced::Task::none()
+            }
+
+            Message::RunAll => self.run_all(),
+            Message::RunFailed => self.run_failed(),
+            Message::StopAll => {
+                self.confirm_stop_all = true;
+                iced::Task::none()
+            }
+            Message::ConfirmStopAll(confirmed) => {
+                self.confirm_stop_all = false;
+                if confirmed {
+                    self.stop_all()
+                } else {
+                    iced::Task::none()
+                }
+            }
+            Message::ConfirmKill(confirmed) => match self.confirm_kill.take() {
+                Some((i, start_time)) if confirmed => self.update(Message::Runner(
+                    i,
+                    runner::Message::ScriptKill { start_time },
+                )),
+                _ => iced::Task::none(),
+            },
+            Message::ClearLogs => {
+                for i in 0..self.runners.len() {
+                    self.clear_logs(i);
+                }
+                self.update_runner_idxs()
+            }
+
+            // Otherwise nothing to do, the redraw refreshes uptimes and fades.
+            Message::Tick => {
+                self.check_stalls(SystemTime::now());
+                self.sample_usage();
+                let now = std::time::Instant::now();
+                for output_rate in &mut self.output_rates {
+                    output_rate.roll(now);
+                }
+                iced::Task::none()
+            }
+        }
+    }
+
+    /// Tick once a second while anything is running, to keep uptimes live,
+    /// and every frame while activity lights are fading.
+    pub fn subscription(&self) -> iced::Subscription<Message> {
+        let uptime = if self.runners.iter().any(runner::Runner::is_running) {
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Tick)
+        } else {
+            iced::Subscription::none()
+        };
+        let fade = if self.runners.iter().any(runner::Runner::is_animating) {
+            iced::window::frames().map(|_| Message::Tick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let close = iced::window::close_requests().map(Message::WindowCloseRequested);
+
+        // Also needed to turn drags of the divider into a share of the width
+        let geometry = iced::event::listen_with(|event, _status, _id| match event {
+            iced::Event::Window(iced::window::Event::Opened { position, size }) => {
+                Some(Message::WindowOpened(position, size))
+            }
+            iced::Event::Window(iced::window::Event::Resized(size)) => {
+                Some(Message::WindowResized(size))
+            }
+            iced::Event::Window(iced::window::Event::Moved(position)) => {
+                Some(Message::WindowMoved(position))
+            }
+            _ => None,
+        });
+
+        let split_drag = if self.split_dragging {
+            iced::event::listen_with(|event, _status, _id| match event {
+                iced::Event::Mouse(iced::mouse::Event::CursorMoved { position }) => {
+                    Some(Message::SplitDragged(position.x))
+                }
+                iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
+                    iced::mouse::Button::Left,
+                )) => Some(Message::SplitDragEnded),
+                _ => None,
+            })
+        } else {
+            iced::Subscription::none()
+        };
+
+        #[cfg(feature = "api")]
+        let api = match self.api_addr {
+            Some(addr) => crate::api::subscription(addr).map(Message::Api),
+            None => iced::Subscription::none(),
+        };
+        #[cfg(not(feature = "api"))]
+        let api = iced::Subscription::none();
+
+        let watches = iced::Subscription::batch(
+            self.runners
+                .iter()
+                .enumerate()
+                .filter(|(_, runner)| !runner.watch.is_empty())
+                .map(|(i, runner)| {
+                    let id = self.runner_ids[i];
+                    crate::watch::subscription(id, runner.watch.clone(), runner.watch_debounce)
+                        .map(Message::WatchTriggered)
+                }),
+        );
+
+        iced::Subscription::batch([uptime, fade, close, geometry, split_drag, watches, api])
+    }
+}
+
+/// Length of the longest runner name
+fn name_width(runners: &[Runner]) -> usize {
+    runners
+        .iter()
+        .map(|runner| runner.name.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// For each runner, the indices of the runners it depends on
+fn dependencies(runners: &[Runner]) -> Vec<Vec<usize>> {
+    runners
+        .iter()
+        .map(|runner| {
+            runner
+                .depends_on
+                .iter()
+                .filter_map(|name| runners.iter().position(|r| &r.name == name))
+                .collect()
+        })
+        .collect()
+}
+
+/// `split`, leaving both sides of the divider at least `MIN_SPLIT` wide
+fn clamp_split(split: f32) -> f32 {
+    if split.is_finite() {
+        split.clamp(MIN_SPLIT, 1.0 - MIN_SPLIT)
+    } else {
+        DEFAULT_SPLIT
+    }
+}
+
+/// Where the export buttons write to: a timestamped file in the current
+/// directory
+fn export_path(format: ExportFormat) -> std::path::PathBuf {
+    let now = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    std::path::PathBuf::from(format!("battlestation-{now}.{}", format.extension()))
+}
+
+/// Matches `http(s)://` URLs in log lines
+static URL: std::sync::LazyLock<regex::Regex> =
+    std::sync::LazyLock::new(|| regex::Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap());
+
+/// Byte ranges of the URLs in `line`, leaving off trailing punctuation
+fn url_ranges(line: &str) -> Vec<std::ops::Range<usize>> {
+    URL.find_iter(line)
+        .map(|m| {
+            let url = m
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+            m.start()..m.start() + url.len()
+        })
+        .collect()
+}
+
+/// A piece of a log line in one style, and the URL it's part of, if any
+type Segment = (
+    std::ops::Range<usize>,
+    ansi::Style,
+    Option<std::ops::Range<usize>>,
+);
+
+/// Split a line by its ANSI spans and by where URLs start and end
+fn segments(line: &str, spans: &[ansi::Span], urls: &[std::ops::Range<usize>]) -> Vec<Segment> {
+    let styled = if spans.is_empty() {
+        vec![(0..line.len(), ansi::Style::default())]
+    } else {
+        let mut start = 0;
+        spans
+            .iter()
+            .map(|span| {
+                let range = start..start + span.len;
+                start += span.len;
+                (range, span.style)
+            })
+            .collect()
+    };
+
+    let mut segments = Vec::new();
+    for (range, style) in styled {
+        let mut start = range.start;
+        while start < range.end {
+            // The URL covering start, or else the next one after it
+            let (end, url) = match urls.iter().find(|url| url.end > start) {
+                Some(url) if url.start <= start => (url.end.min(range.end), Some(url.clone())),
+                Some(url) => (url.start.min(range.end), None),
+                None => (range.end, None),
+            };
+            segments.push((start..end, style, url));
+            start = end;
+        }
+    }
+    segments
+}
+
+/// Runner name colors, picked to stay readable on light and dark themes
+/// and to be told apart from each other
+const RUNNER_PALETTE: [iced::Color; 8] = [
+    iced::color!(0x4e, 0x79, 0xa7),
+    iced::color!(0xf2, 0x8e, 0x2b),
+    iced::color!(0x59, 0xa1, 0x4f),
+    iced::color!(0xb0, 0x7a, 0xa1),
+    iced::color!(0x76, 0xb7, 0xb2),
+    iced::color!(0xe1, 0x57, 0x59),
+    iced::color!(0x9c, 0x75, 0x5f),
+    iced::color!(0xbc, 0xbd, 0x22),
+];
+
+/// How log lines are drawn
+#[derive(Clone, Copy)]
+struct LineStyle<'a> {
+    font: iced::Font,
+    /// Long lines wrap, instead of running off to the right
+    wrap: bool,
+    stdout_glyph: &'a str,
+    stderr_glyph: &'a str,
+    stderr_color: Option<iced::Color>,
+    tab_width: usize,
+}
+
+impl<'a> LineStyle<'a> {
+    fn glyph(&self, io: &IO) -> &'a str {
+        match io {
+            IO::Stdout(_) => self.stdout_glyph,
+            IO::Stderr(_) => self.stderr_glyph,
+        }
+    }
+
+    /// The row's tint: its level's color, or else its stream's
+    fn tint(&self, io: &IO, level_color: Option<iced::Color>) -> Option<iced::Color> {
+        match io {
+            IO::Stdout(_) => level_color,
+            IO::Stderr(_) => level_color.or(self.stderr_color),
+        }
+    }
+}
+
+/// Draw a log line in `style`, coloring each span by its ANSI style.
+/// URLs are underlined, and open in the browser when clicked.
+/// Unstyled text is drawn in `tint`, if given.
+fn styled_line<'a>(
+    line: Cow<'a, str>,
+    spans: &[ansi::Span],
+    tint: Option<iced::Color>,
+    style: LineStyle,
+) -> iced::Element<'a, Message> {
+    let wrapping = if style.wrap {
+        widget::text::Wrapping::WordOrGlyph
+    } else {
+        widget::text::Wrapping::None
+    };
+    let urls = url_ranges(&line);
+    if spans.is_empty() && urls.is_empty() {
+        return widget::text(line)
+            .font(style.font)
+            .color_maybe(tint)
+            .wrapping(wrapping)
+            .into();
+    }
+
+    let spans = segments(&line, spans, &urls)
+        .into_iter()
+        .map(|(range, span_style, url)| {
+            let font = iced::Font {
+                weight: if span_style.bold {
+                    iced::font::Weight::Bold
+                } else {
+                    iced::font::Weight::Normal
+                },
+                ..style.font
+            };
+            widget::span::<Message, _>(line[range].to_string())
+                .font(font)
+                .color_maybe(span_style.fg.map(ansi::Color::to_iced).or(tint))
+                .underline(url.is_some())
+                .link_maybe(url.map(|url| Message::OpenUrl(line[url].to_string())))
+        })
+        .collect::<Vec<_>>();
+    widget::rich_text(spans).wrapping(wrapping).into()
+}
+
+/// The color of the first of `levels` whose token starts `line`, ignoring
+/// leading punctuation so `[WARN]` and `ERROR:` both match. The token must
+/// be a whole word, so `INFORMATION` isn't `INFO`.
+fn level_color(levels: &[(String, iced::Color)], line: &str) -> Option<iced::Color> {
+    if levels.is_empty() {
+        return None;
+    }
+    let line = line.trim_start_matches(|c: char| !c.is_alphanumeric());
+    levels.iter().find_map(|(token, color)| {
+        let rest = line.strip_prefix(token.as_str())?;
+        match rest.chars().next() {
+            Some(c) if c.is_alphanumeric() => None,
+            _ => Some(*color),
+        }
+    })
+}
+
+/// Append output to the partial line in `buf`, returning any lines it
+/// completes. A `\r` rewinds to the start of the line so progress bars
+/// replace themselves. It's kept at the end of `buf` until we know whether
+/// it's part of a `\r\n`. Partial lines longer than `max_len` bytes are
+/// completed without waiting for a newline.
+fn split_lines(buf: &mut String, s: &str, max_len: usize) -> Vec<String> {
+    fn push_text(buf: &mut String, text: &str, max_len: usize, lines: &mut Vec<String>) {
+        if !text.is_empty() {
+            if buf.ends_with('\r') {
+                buf.clear();
+            }
+            buf.push_str(text);
+        }
+
+        while buf.len() > max_len {
+            let mut at = max_len;
+            while !buf.is_char_boundary(at) {
+                at -= 1;
+            }
+            // Always make progress, even if max_len is inside the first char
+            if at == 0 {
+                at = buf.chars().next().map_or(buf.len(), char::len_utf8);
+            }
+            let rest = buf.split_off(at);
+            lines.push(std::mem::replace(buf, rest));
         }
     }
+
+    let mut lines = Vec::new();
+    let mut rest = s;
+    while let Some(n) = rest.find(['\r', '\n']) {
+        push_text(buf, &rest[..n], max_len, &mut lines);
+        if rest.as_bytes()[n] == b'\n' {
+            lines.push(partial_line(&std::mem::take(buf)).to_string());
+        } else if !buf.ends_with('\r') {
+            buf.push('\r');
+        }
+        rest = &rest[n + 1..];
+    }
+    push_text(buf, rest, max_len, &mut lines);
+
+    lines
+}
+
+/// The displayable part of a partial line buffer
+fn partial_line(buf: &str) -> &str {
+    buf.strip_suffix('\r').unwrap_or(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn carriage_returns_replace_the_line() {
+        let mut buf = String::new();
+        assert!(split_lines(&mut buf, "10%\r", usize::MAX).is_empty());
+        assert!(split_lines(&mut buf, "50%", usize::MAX).is_empty());
+        assert_eq!(partial_line(&buf), "50%");
+        assert_eq!(
+            split_lines(&mut buf, "\r100%\ndone\r", usize::MAX),
+            ["100%"]
+        );
+        // \r\n split across chunks is still a single line ending
+        assert_eq!(split_lines(&mut buf, "\nnext", usize::MAX), ["done"]);
+        assert_eq!(buf, "next");
+    }
+
+    #[test]
+    fn long_partial_lines_are_completed() {
+        let mut buf = String::new();
+        assert!(split_lines(&mut buf, "abc", 4).is_empty());
+        assert_eq!(split_lines(&mut buf, "defghij", 4), ["abcd", "efgh"]);
+        assert_eq!(buf, "ij");
+        assert_eq!(split_lines(&mut buf, "k\nl", 4), ["ijk"]);
+        assert_eq!(buf, "l");
+
+        // Never split inside a char
+        let mut buf = String::new();
+        assert_eq!(split_lines(&mut buf, "a\u{e9}\u{e9}", 2), ["a", "\u{e9}"]);
+        assert_eq!(buf, "\u{e9}");
+        let mut buf = String::new();
+        assert_eq!(
+            split_lines(&mut buf, "\u{1f600}\u{1f600}x", 1),
+            ["\u{1f600}", "\u{1f600}"]
+        );
+        assert_eq!(buf, "x");
+    }
+
+    #[test]
+    fn level_tokens_must_start_the_line() {
+        let red = iced::Color::from_rgb8(0xff, 0, 0);
+        let levels = [
+            ("ERROR".to_string(), red),
+            ("INFO".to_string(), iced::Color::WHITE),
+        ];
+        assert_eq!(level_color(&levels, "ERROR: oops"), Some(red));
+        assert_eq!(level_color(&levels, "[ERROR] oops"), Some(red));
+        assert_eq!(level_color(&levels, "ERROR"), Some(red));
+        assert_eq!(level_color(&levels, "INFORMATION"), None);
+        assert_eq!(level_color(&levels, "no ERROR here"), None);
+        assert_eq!(level_color(&[], "ERROR"), None);
+    }
+
+    #[test]
+    fn urls_are_split_out_of_styled_spans() {
+        let line = "Local: http://localhost:5173/. ok";
+        let urls = url_ranges(line);
+        assert_eq!(
+            urls.iter()
+                .map(|url| &line[url.clone()])
+                .collect::<Vec<_>>(),
+            ["http://localhost:5173/"]
+        );
+
+        // A span boundary in the middle of the URL splits it, but both
+        // halves still link to the whole URL
+        let red = ansi::Style {
+            fg: Some(ansi::Color::Red),
+            bold: false,
+        };
+        let spans = [
+            ansi::Span {
+                len: 17,
+                style: ansi::Style::default(),
+            },
+            ansi::Span {
+                len: line.len() - 17,
+                style: red,
+            },
+        ];
+        let pieces = segments(line, &spans, &urls)
+            .into_iter()
+            .map(|(range, style, url)| (&line[range], style == red, url.map(|url| &line[url])))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pieces,
+            [
+                ("Local: ", false, None),
+                ("http://loc", false, Some("http://localhost:5173/")),
+                ("alhost:5173/", true, Some("http://localhost:5173/")),
+                (". ok", true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_all_and_stop_all() {
+        let mut a = Runner::new("a".to_string(), "true".to_string());
+        a.ready_when = Some(regex::Regex::new("ready").unwrap());
+        let mut b = Runner::new("b".to_string(), "true".to_string());
+        b.depends_on = vec!["a".to_string()];
+        let mut app = App::new(vec![a, b]);
+
+        let _ = app.update(Message::RunAll);
+        assert!(app.runners[0].is_running());
+        assert!(app.runners[1].waiting);
+
+        // Nothing stops until confirmed
+        let _ = app.update(Message::StopAll);
+        let _ = app.update(Message::ConfirmStopAll(false));
+        assert!(app.runners[1].waiting);
+
+        let _ = app.update(Message::StopAll);
+        let _ = app.update(Message::ConfirmStopAll(true));
+        assert!(app.runners[1].can_run());
+        assert!(!app.confirm_stop_all);
+    }
+
+    #[test]
+    fn run_failed_only_runs_failed_runners() {
+        let runners = ["ok", "failed", "running"]
+            .map(|name| Runner::new(name.to_string(), "true".to_string()))
+            .into();
+        let mut app = App::new(runners);
+        for (i, status) in [(0, 0), (1, 1)] {
+            let _ = app.update(Message::Runner(i, runner::Message::ScriptRun));
+            let complete = runner::Message::ScriptComplete {
+                status,
+                signal: None,
+                start_time: app.runners[i].start_time().unwrap(),
+                end_time: SystemTime::now(),
+            };
+            let _ = app.update(Message::Runner(i, complete));
+        }
+        let _ = app.update(Message::Runner(2, runner::Message::ScriptRun));
+        let running_since = app.runners[2].start_time();
+
+        let _ = app.update(Message::RunFailed);
+        assert!(!app.runners[0].is_running());
+        assert!(app.runners[1].is_running());
+        assert_eq!(app.runners[2].start_time(), running_since);
+    }
+
+    #[test]
+    #[cfg(feature = "api")]
+    fn runners_are_added_and_removed_by_id() {
+        let runners = ["a", "b"]
+            .map(|name| {
+                let mut runner = Runner::new(name.to_string(), "true".to_string());
+                runner.show_logs = true;
+                runner
+            })
+            .into();
+        let mut app = App::new(runners);
+        let _ = app.update(Message::Runner(
+            1,
+            runner::Message::Stdout("b\n".to_string()),
+        ));
+        let _ = app.update(Message::Runner(1, runner::Message::SetSolo(true)));
+
+        let mut c = Runner::new("c".to_string(), "true".to_string());
+        c.depends_on = vec!["b".to_string()];
+        let _ = app.add_runner(c);
+        assert_eq!(app.runner_ids, [0, 1, 2]);
+        assert_eq!(app.dependencies[2], [1]);
+
+        let _ = app.remove_runner(0);
+        assert_eq!(app.runner_ids, [1, 2]);
+        assert_eq!(app.dependencies[1], [0]);
+        assert_eq!(app.solo, Some(0));
+        assert_eq!(app.logs[0].len(), 1);
+
+        // Messages for the removed runner go nowhere, others find theirs
+        let message = runner::Message::Stdout("a\n".to_string());
+        let _ = app.update(Message::RunnerTask(0, message));
+        let message = runner::Message::Stdout("c\n".to_string());
+        let _ = app.update(Message::RunnerTask(2, message));
+        assert_eq!(
+            app.logs.iter().map(VecDeque::len).collect::<Vec<_>>(),
+            [1, 1]
+        );
+        assert_eq!(app.logs[1][0].1, IO::Stdout("c".to_string()));
+    }
+
+    #[test]
+    fn kills_wait_for_confirmation() {
+        let mut runner = Runner::new("migrate".to_string(), "true".to_string());
+        runner.confirm_kill = true;
+        let mut app = App::new(vec![runner]);
+        let _ = app.update(Message::Runner(0, runner::Message::ScriptRun));
+        let start_time = app.runners[0].start_time().unwrap();
+
+        let confirm = runner::Message::ConfirmKill { start_time };
+        let _ = app.update(Message::Runner(0, confirm.clone()));
+        assert_eq!(app.confirm_kill, Some((0, start_time)));
+        let _ = app.update(Message::ConfirmKill(false));
+        assert_eq!(app.confirm_kill, None);
+        assert!(app.runners[0].is_running());
+
+        let _ = app.update(Message::Runner(0, confirm));
+        let _ = app.update(Message::ConfirmKill(true));
+        assert_eq!(app.confirm_kill, None);
+    }
+
+    #[test]
+    fn title_counts_running_and_failed() {
+        let runners = ["a", "b", "c"]
+            .map(|name| Runner::new(name.to_string(), "true".to_string()))
+            .into();
+        let mut app = App::new(runners);
+        assert_eq!(app.title(), "Battlestation");
+
+        let _ = app.update(Message::Runner(0, runner::Message::ScriptRun));
+        assert_eq!(app.title(), "Battlestation \u{2014} 1 running");
+
+        let now = SystemTime::now();
+        for (i, status) in [(1, 1), (2, 0)] {
+            let complete = runner::Message::ScriptComplete {
+                status,
+                signal: None,
+                start_time: now,
+                end_time: now,
+            };
+            let _ = app.update(Message::Runner(i, complete));
+        }
+        assert_eq!(app.title(), "Battlestation \u{2014} 1 running, 1 failed");
+    }
+
+    #[test]
+    fn runners_without_a_color_get_one_from_the_palette() {
+        let red = iced::Color::from_rgb8(0xff, 0, 0);
+        let mut runners: Vec<Runner> = (0..RUNNER_PALETTE.len() + 1)
+            .map(|i| Runner::new(i.to_string(), "true".to_string()))
+            .collect();
+        runners[1].color = Some(red);
+        let app = App::new(runners);
+        assert_eq!(app.runner_color(0), RUNNER_PALETTE[0]);
+        assert_eq!(app.runner_color(1), red);
+        assert_eq!(app.runner_color(2), RUNNER_PALETTE[2]);
+        assert_eq!(app.runner_color(RUNNER_PALETTE.len()), RUNNER_PALETTE[0]);
+    }
+
+    #[test]
+    fn partial_line_is_flushed_on_complete() {
+        let runner = Runner::new("test".to_string(), "printf 'a\\nb'".to_string());
+        let mut app = App::new(vec![runner]);
+
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stdout("a\nb".to_string()),
+        ));
+        assert_eq!(app.logs[0].len(), 1);
+        assert_eq!(app.runner_stdout_buf[0], "b");
+
+        let now = SystemTime::now();
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::ScriptComplete {
+                status: 0,
+                signal: None,
+                start_time: now,
+                end_time: now,
+            },
+        ));
+        let lines = app.logs[0]
+            .iter()
+            .map(|(_, io, ..)| io.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [IO::Stdout("a".to_string()), IO::Stdout("b".to_string())]
+        );
+        assert!(app.runner_stdout_buf[0].is_empty());
+    }
+
+    #[test]
+    fn relative_timestamps_count_from_the_run_start() {
+        let runner = Runner::new("test".to_string(), "true".to_string());
+        let mut app = App::new(vec![runner]);
+        app.timestamp_mode = TimestampMode::Relative;
+
+        let t0 = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100);
+        let ms = std::time::Duration::from_millis;
+        app.run_starts[0] = vec![t0, t0 + ms(10_000)];
+
+        assert_eq!(app.timestamp(0, t0 + ms(1_500)).unwrap(), "+1.500s");
+        assert_eq!(app.timestamp(0, t0 + ms(12_305)).unwrap(), "+2.305s");
+        // Lines from before any known run start count from themselves
+        assert_eq!(app.timestamp(0, t0 - ms(5)).unwrap(), "+0.000s");
+    }
+
+    #[test]
+    fn logs_are_capped_at_max_log_lines() {
+        let mut runner = Runner::new("test".to_string(), "seq 5".to_string());
+        runner.show_logs = true;
+        runner.max_log_lines = Some(3);
+        let mut app = App::new(vec![runner]);
+        let _ = app.update_runner_idxs();
+        let _ = app.update(Message::ScrollState(
+            scroll_state::Message::SetEnableUpdates(true),
+        ));
+
+        for i in 1..=5 {
+            let _ = app.update(Message::Runner(
+                0,
+                runner::Message::Stdout(format!("{i}\n")),
+            ));
+
+            // Shown lines still point at the right entries after eviction
+            let shown = app
+                .scroll_state
+                .logs
+                .iter()
+                .map(|ssl| app.logs[ssl.runner_idx][ssl.log_pos].1.clone())
+                .collect::<Vec<_>>();
+            let expected = (i.max(3) - 2..=i)
+                .map(|j| IO::Stdout(j.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(shown, expected);
+        }
+        assert_eq!(app.logs[0].len(), 3);
+    }
+
+    #[test]
+    fn ansi_codes_are_stripped_or_kept_as_spans() {
+        let runners = [false, true].map(|strip_ansi| {
+            let mut runner = Runner::new("test".to_string(), "true".to_string());
+            runner.strip_ansi = strip_ansi;
+            runner
+        });
+        let mut app = App::new(runners.into());
+
+        for i in 0..2 {
+            let line = "\x1b[31mred\x1b[0m\n".to_string();
+            let _ = app.update(Message::Runner(i, runner::Message::Stdout(line)));
+            assert_eq!(app.logs[i][0].1, IO::Stdout("red".to_string()));
+        }
+        assert_eq!(app.logs[0][0].2.len(), 1);
+        assert!(app.logs[1][0].2.is_empty());
+    }
+
+    #[test]
+    fn log_filter_keeps_matching_lines_in_order() {
+        let runners = ["a", "b"].map(|name| {
+            let mut runner = Runner::new(name.to_string(), "true".to_string());
+            runner.show_logs = true;
+            runner
+        });
+        let mut app = App::new(runners.into());
+        let enable_updates = Message::ScrollState(scroll_state::Message::SetEnableUpdates(true));
+        let _ = app.update_runner_idxs();
+        let _ = app.update(enable_updates.clone());
+
+        for (i, line) in [
+            (0, "GET /"),
+            (1, "tick"),
+            (1, "GET /a"),
+            (0, "tick"),
+            (0, "GET /b"),
+        ] {
+            let _ = app.update(Message::Runner(
+                i,
+                runner::Message::Stdout(format!("{line}\n")),
+            ));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let _ = app.update(Message::SetLogFilter("GET".to_string()));
+        // Stand in for the tasks set_filter chains after scrolling
+        let _ = app.update(enable_updates);
+        let _ = app.update(Message::ScrollState(scroll_state::Message::UpdateLogs));
+
+        let shown = app
+            .scroll_state
+            .logs
+            .iter()
+            .map(|ssl| app.logs[ssl.runner_idx][ssl.log_pos].1.line())
+            .collect::<Vec<_>>();
+        assert_eq!(shown, ["GET /", "GET /a", "GET /b"]);
+        assert_eq!(app.scroll_state.total_lines, 3);
+
+        assert_eq!(
+            app.log_text(&app.scroll_state.merged(&app.logs)),
+            "a[>] GET /\nb[>] GET /a\na[>] GET /b\n"
+        );
+
+        // Streams compose with the search; none of the GETs were stderr
+        let _ = app.update(Message::SetLogFilterStreams(scroll_state::Streams::Stderr));
+        let _ = app.update(Message::ScrollState(
+            scroll_state::Message::SetEnableUpdates(true),
+        ));
+        let _ = app.update(Message::ScrollState(scroll_state::Message::UpdateLogs));
+        assert!(app.scroll_state.logs.is_empty());
+    }
+
+    #[test]
+    fn log_search_modes() {
+        let search = |query: &str, regex, case_insensitive| {
+            LogSearch {
+                query: query.to_string(),
+                regex,
+                case_insensitive,
+                ..LogSearch::default()
+            }
+            .pattern()
+        };
+        let matches = |pattern: Result<Option<regex::Regex>, _>, line| {
+            pattern.unwrap().unwrap().is_match(line)
+        };
+
+        assert!(search("", true, false).unwrap().is_none());
+        assert!(matches(search("ERROR|WARN", true, false), "WARN: disk"));
+        assert!(!matches(search("ERROR|WARN", false, false), "WARN: disk"));
+        assert!(matches(search("error", false, true), "ERROR: oops"));
+        assert!(!matches(search("error", false, false), "ERROR: oops"));
+        assert!(search("(", true, false).is_err());
+    }
+
+    #[test]
+    fn export_writes_every_line_in_order() {
+        let runners = ["a", "b"].map(|name| Runner::new(name.to_string(), "true".to_string()));
+        let mut app = App::new(runners.into());
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stdout("one\n".to_string()),
+        ));
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let _ = app.update(Message::Runner(
+            1,
+            runner::Message::Stderr("two\n".to_string()),
+        ));
+
+        let path = std::env::temp_dir().join(format!(
+            "battlestation-export-test-{}.log",
+            std::process::id()
+        ));
+        let _ = app.update(Message::ExportLogs(path.clone()));
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines = exported
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["a [>] one", "b [!] two"]);
+
+        let _ = app.update(Message::SetExportFormat(ExportFormat::JsonLines));
+        let _ = app.update(Message::ExportLogs(path.clone()));
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines = exported
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["runner"], "b");
+        assert_eq!(lines[1]["stream"], "stderr");
+        assert_eq!(lines[1]["line"], "two");
+        assert!(lines[1]["ts"].is_string());
+        assert_eq!(
+            ExportFormat::from_path(std::path::Path::new("out.jsonl")),
+            ExportFormat::JsonLines
+        );
+    }
+
+    #[test]
+    fn copies_and_exports_use_the_configured_glyphs() {
+        let mut app = App::new(vec![Runner::new("a".to_string(), "true".to_string())]);
+        app.stdout_glyph = "out".to_string();
+        app.stderr_glyph = "err".to_string();
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stdout("one\n".to_string()),
+        ));
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stderr("two\n".to_string()),
+        ));
+        let logs = app.all_logs();
+        assert_eq!(app.log_text(&logs), "aout one\naerr two\n");
+
+        let path = std::env::temp_dir().join(format!(
+            "battlestation-glyph-export-test-{}.log",
+            std::process::id()
+        ));
+        app.export_logs(&path, ExportFormat::Text, &logs).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines = exported
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["a out one", "a err two"]);
+    }
+
+    #[test]
+    fn clearing_one_runner_keeps_the_others() {
+        let runners = ["a", "b"].map(|name| {
+            let mut runner = Runner::new(name.to_string(), "true".to_string());
+            runner.show_logs = true;
+            runner
+        });
+        let mut app = App::new(runners.into());
+        let enable_updates = Message::ScrollState(scroll_state::Message::SetEnableUpdates(true));
+        let _ = app.update_runner_idxs();
+        let _ = app.update(enable_updates.clone());
+
+        for i in 0..2 {
+            let _ = app.update(Message::Runner(
+                i,
+                runner::Message::Stdout("1\n2\n3".to_string()),
+            ));
+        }
+        let _ = app.update(Message::Runner(0, runner::Message::ClearLogs));
+        // Stand in for the tasks set_runner_idxs chains after scrolling
+        let _ = app.update(enable_updates);
+        let _ = app.update(Message::ScrollState(scroll_state::Message::UpdateLogs));
+
+        assert!(app.logs[0].is_empty());
+        assert!(app.runner_stdout_buf[0].is_empty());
+        assert_eq!(app.logs[1].len(), 2);
+        assert_eq!(app.runner_stdout_buf[1], "3");
+        assert_eq!(app.scroll_state.logs.len(), 2);
+        assert!(app.scroll_state.logs.iter().all(|ssl| ssl.runner_idx == 1));
+    }
+
+    #[test]
+    fn solo_shows_one_runner_then_restores() {
+        let runners = ["a", "b", "c"].map(|name| {
+            let mut runner = Runner::new(name.to_string(), "true".to_string());
+            runner.show_logs = name != "c";
+            runner
+        });
+        let mut app = App::new(runners.into());
+        let shown = |app: &App| {
+            (0..app.runners.len())
+                .filter(|&i| app.runner_logs_shown(i))
+                .collect::<Vec<_>>()
+        };
+
+        let _ = app.update(Message::Runner(2, runner::Message::SetSolo(true)));
+        assert_eq!(shown(&app), [2]);
+
+        let _ = app.update(Message::Runner(2, runner::Message::SetSolo(false)));
+        assert_eq!(shown(&app), [0, 1]);
+    }
+
+    #[test]
+    fn repeated_lines_are_collapsed_if_asked() {
+        let mut runner = Runner::new("a".to_string(), "true".to_string());
+        runner.collapse_repeats = true;
+        let mut app = App::new(vec![runner]);
+        let stdout = |line: &str| Message::Runner(0, runner::Message::Stdout(format!("{line}\n")));
+        for line in ["retrying", "retrying", "retrying", "ok", "retrying"] {
+            let _ = app.update(stdout(line));
+        }
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stderr("ok\n".to_string()),
+        ));
+
+        assert_eq!(
+            app.log_text(&app.all_logs()),
+            "a[>] retrying \u{d7}3\na[>] ok\na[>] retrying\na[!] ok\n"
+        );
+
+        let _ = app.update(Message::Runner(0, runner::Message::ClearLogs));
+        assert!(app.repeats.is_empty());
+    }
+
+    #[test]
+    fn runner_search_hides_runners_but_not_their_logs() {
+        let mut runners = ["web", "queue-worker", "db"]
+            .map(|name| Runner::new(name.to_string(), "true".to_string()));
+        runners[2].tags = vec!["Workers".to_string()];
+        for runner in &mut runners {
+            runner.show_logs = true;
+        }
+        let mut app = App::new(runners.into());
+        let listed = |app: &App| {
+            (0..app.runners.len())
+                .filter(|&i| app.runner_listed(i))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed(&app), [0, 1, 2]);
+
+        let _ = app.update(Message::SetRunnerQuery("WORKER".to_string()));
+        assert_eq!(listed(&app), [1, 2]);
+        assert!(app.runner_logs_shown(0));
+
+        let _ = app.update(Message::SetTagFilter(Some("Workers".to_string())));
+        assert_eq!(listed(&app), [2]);
+    }
+
+    #[test]
+    fn runners_are_sorted_without_moving_them() {
+        let runners = ["web", "Api", "db", "cache"]
+            .map(|name| Runner::new(name.to_string(), "true".to_string()));
+        let mut app = App::new(runners.into());
+        let _ = app.update(Message::Runner(2, runner::Message::ScriptRun));
+        let complete = runner::Message::ScriptComplete {
+            status: 1,
+            signal: None,
+            start_time: app.runners[2].start_time().unwrap(),
+            end_time: SystemTime::now(),
+        };
+        let _ = app.update(Message::Runner(2, complete));
+        let _ = app.update(Message::Runner(3, runner::Message::ScriptRun));
+        assert_eq!(app.listed_runners(), [0, 1, 2, 3]);
+
+        let _ = app.update(Message::SetRunnerSort(RunnerSort::Name));
+        assert_eq!(app.listed_runners(), [1, 3, 2, 0]);
+
+        let _ = app.update(Message::SetRunnerSort(RunnerSort::Status));
+        assert_eq!(app.listed_runners(), [3, 2, 0, 1]);
+        assert_eq!(app.runners[3].name, "cache");
+    }
+
+    #[test]
+    fn dragging_the_divider_sets_the_split() {
+        let mut app = App::new(Vec::new());
+        let _ = app.update(Message::WindowResized(iced::Size::new(1000.0, 800.0)));
+        let _ = app.update(Message::SplitDragStarted);
+        let _ = app.update(Message::SplitDragged(500.0));
+        assert_eq!(app.split, 0.5);
+        let _ = app.update(Message::SplitDragged(-20.0));
+        assert_eq!(app.split, MIN_SPLIT);
+        let _ = app.update(Message::SplitDragEnded);
+        assert!(!app.split_dragging);
+        assert_eq!(clamp_split(f32::NAN), DEFAULT_SPLIT);
+    }
+
+    #[test]
+    fn quiet_runs_are_marked_as_stalled() {
+        let mut runner = Runner::new("test".to_string(), "sleep 10".to_string());
+        runner.stall_timeout = Some(std::time::Duration::from_secs(1));
+        let mut app = App::new(vec![runner]);
+        let _ = app.update(Message::Runner(0, runner::Message::ScriptRun));
+        let start_time = app.runners[0].start_time().unwrap();
+        app.check_stalls(start_time + std::time::Duration::from_millis(500));
+        assert!(!app.stalled[0]);
+        app.check_stalls(start_time + std::time::Duration::from_secs(2));
+        assert!(app.stalled[0]);
+
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stdout("a\n".to_string()),
+        ));
+        assert!(!app.stalled[0]);
+        let now = SystemTime::now();
+        app.check_stalls(now + std::time::Duration::from_millis(500));
+        assert!(!app.stalled[0]);
+        app.check_stalls(now + std::time::Duration::from_secs(2));
+        assert!(app.stalled[0]);
+    }
+
+    #[test]
+    fn scheduled_runs_are_skipped_while_running() {
+        let mut runner = Runner::new("test".to_string(), "sleep 10".to_string());
+        runner.schedule = Some(runner::Schedule::Every(std::time::Duration::from_secs(60)));
+        let mut app = App::new(vec![runner]);
+        let _ = app.update(Message::ScheduleDue(0, SystemTime::now()));
+        let start_time = app.runners[0].start_time().unwrap();
+        let _ = app.update(Message::ScheduleDue(0, SystemTime::now()));
+        assert_eq!(app.runners[0].start_time(), Some(start_time));
+    }
+
+    #[test]
+    fn closing_waits_for_runners_to_stop() {
+        let runners = ["a", "b"]
+            .map(|name| Runner::new(name.to_string(), "sleep 60".to_string()))
+            .into();
+        let mut app = App::new(runners);
+        let _ = app.update(Message::Runner(0, runner::Message::ScriptRun));
+        let start_time = app.runners[0].start_time().unwrap();
+
+        let id = iced::window::Id::unique();
+        let _ = app.update(Message::WindowCloseRequested(id));
+        assert_eq!(app.closing, Some(id));
+        let _ = app.update(Message::ScheduleDue(1, SystemTime::now()));
+        assert!(!app.runners[1].is_running());
+
+        let complete = runner::Message::ScriptComplete {
+            status: 143,
+            signal: None,
+            start_time,
+            end_time: SystemTime::now(),
+        };
+        let _ = app.update(Message::Runner(0, complete));
+        assert_eq!(app.closing, None);
+    }
+
+    #[test]
+    fn closing_keeps_restart_policies_in_the_session() {
+        let dir = std::env::temp_dir().join(format!(
+            "battlestation-close-session-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("battlestation.toml.session.json");
+        let mut runner = Runner::new("a".to_string(), "sleep 60".to_string());
+        runner.restart = runner::RestartPolicy::OnFailure;
+        let mut app = App::new(vec![runner]);
+        app.session_path = Some(path.clone());
+        let _ = app.update(Message::Runner(0, runner::Message::ScriptRun));
+        let start_time = app.runners[0].start_time().unwrap();
+
+        let _ = app.update(Message::WindowCloseRequested(iced::window::Id::unique()));
+        let complete = runner::Message::ScriptComplete {
+            status: 143,
+            signal: None,
+            start_time,
+            end_time: SystemTime::now(),
+        };
+        let _ = app.update(Message::Runner(0, complete));
+        let session = crate::session::load(&path);
+        assert_eq!(
+            session.runners["a"].restart,
+            runner::RestartPolicy::OnFailure
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sessions_are_saved_and_restored() {
+        let runners = || {
+            ["a", "b"].map(|name| {
+                let mut runner = Runner::new(name.to_string(), "true".to_string());
+                runner.tags = vec![name.to_string()];
+                runner
+            })
+        };
+        let mut app = App::new(runners().into());
+        let _ = app.update(Message::Runner(1, runner::Message::SetShowLogs(true)));
+        let _ = app.update(Message::Runner(
+            1,
+            runner::Message::SetRestart(runner::RestartPolicy::Always),
+        ));
+        let _ = app.update(Message::SetTagFilter(Some("b".to_string())));
+        let _ = app.update(Message::ScrollState(scroll_state::Message::SetFollow(
+            Follow::Never,
+        )));
+        let session = app.session();
+
+        let mut app = App::new(runners().into());
+        let _ = app.restore_session(session);
+        assert!(!app.runners[0].show_logs);
+        assert!(app.runners[1].show_logs);
+        assert_eq!(app.runners[1].restart, runner::RestartPolicy::Always);
+        assert_eq!(app.tag_filter.as_deref(), Some("b"));
+        assert_eq!(app.scroll_state.follow(), Follow::Never);
+    }
+
+    #[test]
+    fn window_changes_are_saved_once_settled() {
+        let dir = std::env::temp_dir().join(format!(
+            "battlestation-window-save-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("battlestation.toml.session.json");
+        let mut app = App::new(Vec::new());
+        app.session_path = Some(path.clone());
+
+        let _ = app.update(Message::WindowResized(iced::Size::new(800.0, 600.0)));
+        let _ = app.update(Message::WindowMoved(iced::Point::new(10.0, 20.0)));
+        let _ = app.update(Message::WindowSettled(1));
+        assert!(!path.exists());
+
+        let _ = app.update(Message::WindowSettled(2));
+        let window = crate::session::load(&path).window.unwrap();
+        assert_eq!((window.width, window.position), (800.0, Some((10.0, 20.0))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lines_are_unread_while_scrolled_up() {
+        let runners = ["a", "b"].map(|name| {
+            let mut runner = Runner::new(name.to_string(), "true".to_string());
+            runner.show_logs = name == "a";
+            runner
+        });
+        let mut app = App::new(runners.into());
+        let _ = app.update_runner_idxs();
+
+        let _ = app.update(Message::Runner(
+            0,
+            runner::Message::Stdout("1\n".to_string()),
+        ));
+        assert_eq!(app.scroll_state.unread(), 0);
+
+        app.scroll_state.anchor_y = widget::scrollable::Anchor::Start;
+        for i in 0..2 {
+            let _ = app.update(Message::Runner(
+                i,
+                runner::Message::Stdout("2\n3\n".to_string()),
+            ));
+        }
+        // Hidden runners don't count
+        assert_eq!(app.scroll_state.unread(), 2);
+
+        let _ = app.update(Message::ScrollState(scroll_state::Message::JumpToEnd));
+        assert_eq!(app.scroll_state.anchor_y, widget::scrollable::Anchor::End);
+        assert_eq!(app.scroll_state.unread(), 0);
+    }
 }
 
 mod scroll_state {
-    use crate::app::IO;
+    use crate::app::{IO, Log};
 
     use iced::widget;
+    use std::collections::VecDeque;
     use std::time::SystemTime;
 
+    pub const DEFAULT_OVERSCAN: usize = 10;
+
     pub struct ScrollState {
         pub id: widget::scrollable::Id,
         pub space_after: f32,
@@ -225,8 +3212,37 @@ mod scroll_state {
         pub logs: Vec<ScrollStateLog>,
         pub viewport: Option<Viewport>,
         runner_idxs: Vec<usize>,
-        cursors: Vec<usize>,
+        /// Every line the pane can show, in order. Kept up to date as
+        /// lines arrive, and rebuilt when the runners or filter change.
+        index: Vec<IndexEntry>,
+        /// Lines evicted from the front of each selected runner's log
+        /// since the index was built
+        evicted: Vec<usize>,
+        /// The runners or filter changed, so the index needs rebuilding
+        index_stale: bool,
+        /// The index still holds some evicted lines
+        index_has_evicted: bool,
+        /// Scroll to the first line at or after this once the index is
+        /// rebuilt, to keep the same place in a new runner selection
+        seek: Option<SystemTime>,
+        filter: LogFilter,
+        /// Lines shown in total, i.e. matches if filtering
+        pub total_lines: usize,
+        /// Lines drawn beyond each edge of the viewport, so fast scrolling
+        /// doesn't show gaps before the next update
+        pub overscan: usize,
+        /// Position in the merged timeline of the first line in `logs`,
+        /// i.e. the number of lines `space_before` stands in for
+        pub first_line: usize,
         enable_updates: bool,
+        /// Draw only the lines near the viewport. Otherwise every line is
+        /// drawn, for when lines aren't all `line_height` tall.
+        virtualized: bool,
+        /// Lines shown since scrolling away from the end
+        unread: usize,
+        follow: Follow,
+        /// The application's text size, which rows are drawn at
+        pub text_size: f32,
     }
 
     #[derive(Debug)]
@@ -236,16 +3252,89 @@ mod scroll_state {
         pub bounds: iced::Rectangle,
     }
 
+    /// Which output streams are shown
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum Streams {
+        #[default]
+        Both,
+        Stdout,
+        Stderr,
+    }
+
+    /// Which log lines are shown
+    #[derive(Debug, Clone, Default)]
+    pub struct LogFilter {
+        /// Only lines matching this are shown, if given
+        pub pattern: Option<regex::Regex>,
+        pub streams: Streams,
+    }
+
+    impl LogFilter {
+        pub fn is_active(&self) -> bool {
+            self.pattern.is_some() || self.streams != Streams::Both
+        }
+
+        pub fn matches(&self, io: &IO) -> bool {
+            let stream_shown = matches!(
+                (self.streams, io),
+                (Streams::Both, _)
+                    | (Streams::Stdout, IO::Stdout(_))
+                    | (Streams::Stderr, IO::Stderr(_))
+            );
+            stream_shown
+                && self
+                    .pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.is_match(io.line()))
+        }
+    }
+
     pub struct ScrollStateLog {
         pub runner_idx: usize,
         pub log_pos: usize,
     }
 
-    #[derive(Debug)]
+    /// A line in the merged index. Entries are ordered by `(time, seq)`.
+    struct IndexEntry {
+        time: SystemTime,
+        seq: u64,
+        /// Position of the runner in `runner_idxs`
+        i: usize,
+        /// Position in the runner's log, counting lines evicted since the
+        /// index was built, so it doesn't change with evictions
+        pos: usize,
+    }
+
+    #[derive(Debug, Clone)]
     pub enum Message {
         OnScroll(widget::scrollable::Viewport),
         UpdateLogs,
         SetEnableUpdates(bool),
+        JumpToEnd,
+        SetFollow(Follow),
+    }
+
+    /// Whether the pane keeps up with new lines
+    #[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Follow {
+        /// Follow when scrolled to the end, and stop when scrolled away
+        #[default]
+        Auto,
+        /// Stay at the newest line
+        Always,
+        /// Stay where scrolled to, even at the end
+        Never,
+    }
+
+    impl Follow {
+        pub fn next(self) -> Follow {
+            match self {
+                Follow::Auto => Follow::Always,
+                Follow::Always => Follow::Never,
+                Follow::Never => Follow::Auto,
+            }
+        }
     }
 
     impl ScrollState {
@@ -257,22 +3346,34 @@ mod scroll_state {
                 runner_idxs: Vec::new(),
                 logs: Vec::new(),
                 viewport: None,
-                cursors: Vec::new(),
+                index: Vec::new(),
+                evicted: Vec::new(),
+                index_stale: true,
+                index_has_evicted: false,
+                seek: None,
+                filter: LogFilter::default(),
+                total_lines: 0,
+                overscan: DEFAULT_OVERSCAN,
+                first_line: 0,
                 anchor_y: widget::scrollable::Anchor::End,
                 enable_updates: true,
+                virtualized: true,
+                unread: 0,
+                follow: Follow::default(),
+                text_size: iced::Settings::default().default_text_size.0,
             }
         }
 
-        fn line_height() -> f32 {
-            let iced::Pixels(line_height) = widget::text::LineHeight::default()
-                .to_absolute(iced::Settings::default().default_text_size);
+        fn line_height(&self) -> f32 {
+            let iced::Pixels(line_height) =
+                widget::text::LineHeight::default().to_absolute(self.text_size.into());
             line_height
         }
 
         pub fn update(
             &mut self,
             message: Message,
-            runner_logs: &[Vec<(SystemTime, IO)>],
+            runner_logs: &[VecDeque<Log>],
         ) -> iced::Task<Message> {
             match message {
                 Message::UpdateLogs => self.update_logs(runner_logs),
@@ -303,46 +3404,28 @@ mod scroll_state {
                     let update_task = self.update_logs(runner_logs);
 
                     // allow anchor release
-                    let line_height = Self::line_height();
+                    let line_height = self.line_height();
+                    let (to_end, to_start) = match self.follow {
+                        Follow::Auto => (
+                            viewport.absolute_offset_reversed().y < 2.1 * line_height,
+                            viewport.absolute_offset().y > 2.1 * line_height,
+                        ),
+                        Follow::Always => (true, false),
+                        Follow::Never => (false, true),
+                    };
                     let scroll_task = match self.anchor_y {
-                        widget::scrollable::Anchor::Start => {
-                            if viewport.absolute_offset_reversed().y < 2.1 * line_height {
-                                self.anchor_y = widget::scrollable::Anchor::End;
-                                for i in 0..self.cursors.len() {
-                                    let len = runner_logs[self.runner_idxs[i]].len();
-                                    self.cursors[i] = len - self.cursors[i];
-                                }
-
-                                self.enable_updates = false;
-                                widget::scrollable::scroll_to(
-                                    self.id.clone(),
-                                    widget::scrollable::AbsoluteOffset { x: 0.0, y: 0.0 },
-                                )
-                                .chain(iced::Task::done(Message::SetEnableUpdates(true)))
-                                .chain(iced::Task::done(Message::UpdateLogs))
-                            } else {
-                                iced::Task::none()
-                            }
+                        widget::scrollable::Anchor::Start if to_end => self.anchor_to_end(),
+                        widget::scrollable::Anchor::End if to_start => {
+                            self.anchor_to_start(viewport.absolute_offset_reversed())
                         }
-                        widget::scrollable::Anchor::End => {
-                            if viewport.absolute_offset().y > 2.1 * line_height {
-                                self.anchor_y = widget::scrollable::Anchor::Start;
-                                for i in 0..self.cursors.len() {
-                                    let len = runner_logs[self.runner_idxs[i]].len();
-                                    self.cursors[i] = len - self.cursors[i];
-                                }
-
-                                self.enable_updates = false;
-                                widget::scrollable::scroll_to(
-                                    self.id.clone(),
-                                    viewport.absolute_offset_reversed(),
-                                )
-                                .chain(iced::Task::done(Message::SetEnableUpdates(true)))
-                                .chain(iced::Task::done(Message::UpdateLogs))
-                            } else {
-                                iced::Task::none()
-                            }
+                        // Scrolled away while pinned, so snap back
+                        widget::scrollable::Anchor::End
+                            if self.follow == Follow::Always
+                                && viewport.absolute_offset().y > 0.0 =>
+                        {
+                            self.scroll_to(widget::scrollable::AbsoluteOffset { x: 0.0, y: 0.0 })
                         }
+                        _ => iced::Task::none(),
                     };
 
                     iced::Task::batch([update_task, scroll_task])
@@ -352,32 +3435,239 @@ mod scroll_state {
                     self.enable_updates = v;
                     iced::Task::none()
                 }
+
+                Message::JumpToEnd => self.reset(),
+
+                Message::SetFollow(follow) => {
+                    self.follow = follow;
+                    match follow {
+                        Follow::Auto => iced::Task::none(),
+                        Follow::Always => self.reset(),
+                        // Hold the current position from the top, which
+                        // new lines don't move
+                        Follow::Never => match (&self.viewport, self.anchor_y) {
+                            (Some(viewport), widget::scrollable::Anchor::End) => {
+                                self.anchor_to_start(viewport.offset_top)
+                            }
+                            _ => iced::Task::none(),
+                        },
+                    }
+                }
+            }
+        }
+
+        pub fn follow(&self) -> Follow {
+            self.follow
+        }
+
+        fn anchor_to_end(&mut self) -> iced::Task<Message> {
+            self.anchor_y = widget::scrollable::Anchor::End;
+            self.unread = 0;
+            self.scroll_to(widget::scrollable::AbsoluteOffset { x: 0.0, y: 0.0 })
+        }
+
+        /// Anchor to the start, `offset` from the top
+        fn anchor_to_start(
+            &mut self,
+            offset: widget::scrollable::AbsoluteOffset,
+        ) -> iced::Task<Message> {
+            self.anchor_y = widget::scrollable::Anchor::Start;
+            self.scroll_to(offset)
+        }
+
+        /// Scroll to `offset` from the anchor, then update the logs there
+        fn scroll_to(&mut self, offset: widget::scrollable::AbsoluteOffset) -> iced::Task<Message> {
+            self.enable_updates = false;
+            widget::scrollable::scroll_to(self.id.clone(), offset)
+                .chain(iced::Task::done(Message::SetEnableUpdates(true)))
+                .chain(iced::Task::done(Message::UpdateLogs))
+        }
+
+        /// The number of new lines since scrolling away from the end
+        pub fn unread(&self) -> usize {
+            self.unread
+        }
+
+        /// Add `log`, just pushed to `runner_idx`'s log at `log_pos`, to
+        /// the index if it's shown. It counts as unread if scrolled away
+        /// from the end.
+        pub fn line_added(&mut self, runner_idx: usize, log_pos: usize, log: &Log) {
+            let Some(i) = self.runner_idxs.iter().position(|&r| r == runner_idx) else {
+                return;
+            };
+            if !self.filter.matches(&log.1) {
+                return;
+            }
+            if self.anchor_y == widget::scrollable::Anchor::Start {
+                self.unread += 1;
+            }
+            // The rebuild will pick it up
+            if self.index_stale {
+                return;
             }
+
+            let entry = IndexEntry {
+                time: log.0,
+                seq: log.3,
+                i,
+                pos: log_pos + self.evicted[i],
+            };
+            // Lines almost always arrive in time order, making this a push
+            let at = self
+                .index
+                .partition_point(|e| (e.time, e.seq) < (entry.time, entry.seq));
+            self.index.insert(at, entry);
+        }
+
+        pub fn filter(&self) -> &LogFilter {
+            &self.filter
+        }
+
+        /// Show only lines passing `filter`.
+        /// Like a new runner selection, this jumps back to the end.
+        pub fn set_filter(&mut self, filter: LogFilter) -> iced::Task<Message> {
+            self.filter = filter;
+            self.index_stale = true;
+            self.reset()
         }
 
         pub fn set_runner_idxs(
             &mut self,
             runner_idxs: impl Iterator<Item = usize>,
         ) -> iced::Task<Message> {
+            // Scrolled away, stay at the same time in the new selection
+            let top_time = match (self.anchor_y, &self.viewport) {
+                (widget::scrollable::Anchor::Start, Some(viewport)) if !self.index_stale => {
+                    let top = to_lines(
+                        (viewport.offset_top.y / self.line_height()).floor(),
+                        usize::MAX,
+                    );
+                    self.index.get(top).map(|entry| entry.time)
+                }
+                _ => None,
+            };
+
             self.runner_idxs.clear();
             self.runner_idxs.extend(runner_idxs);
+            self.index_stale = true;
+            match top_time {
+                Some(time) => {
+                    self.seek = Some(time);
+                    iced::Task::done(Message::UpdateLogs)
+                }
+                None => self.reset(),
+            }
+        }
+
+        /// Switch culling lines outside the viewport on or off.
+        /// Content heights change, so this jumps back to the end.
+        pub fn set_virtualized(&mut self, virtualized: bool) -> iced::Task<Message> {
+            self.virtualized = virtualized;
+            self.reset()
+        }
+
+        /// Anchor back to the end, with the viewport starting over
+        fn reset(&mut self) -> iced::Task<Message> {
             self.anchor_y = widget::scrollable::Anchor::End;
-            self.cursors = vec![0; self.runner_idxs.len()];
+            self.unread = 0;
             self.viewport = None;
+            self.seek = None;
 
-            self.enable_updates = false;
-            widget::scrollable::scroll_to(
-                self.id.clone(),
-                widget::scrollable::AbsoluteOffset { x: 0.0, y: 0.0 },
-            )
-            .chain(iced::Task::done(Message::SetEnableUpdates(true)))
-            .chain(iced::Task::done(Message::UpdateLogs))
+            self.scroll_to(widget::scrollable::AbsoluteOffset { x: 0.0, y: 0.0 })
         }
 
-        pub fn update_logs(
-            &mut self,
-            runner_logs: &[Vec<(SystemTime, IO)>],
-        ) -> iced::Task<Message> {
+        /// Every line the pane would show with enough scrolling, in order
+        pub fn merged(&self, runner_logs: &[VecDeque<Log>]) -> Vec<ScrollStateLog> {
+            if self.index_stale {
+                return merge(runner_logs, &self.runner_idxs, &self.filter);
+            }
+            self.index
+                .iter()
+                .filter(|entry| entry.pos >= self.evicted[entry.i])
+                .map(|entry| self.index_log(entry))
+                .collect()
+        }
+
+        fn index_log(&self, entry: &IndexEntry) -> ScrollStateLog {
+            ScrollStateLog {
+                runner_idx: self.runner_idxs[entry.i],
+                log_pos: entry.pos - self.evicted[entry.i],
+            }
+        }
+
+        /// Merge the selected runners' lines passing the filter
+        fn rebuild_index(&mut self, runner_logs: &[VecDeque<Log>]) {
+            let filter = &self.filter;
+            self.index = self
+                .runner_idxs
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &runner_idx)| {
+                    runner_logs[runner_idx]
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, log)| filter.matches(&log.1))
+                        .map(move |(pos, log)| IndexEntry {
+                            time: log.0,
+                            seq: log.3,
+                            i,
+                            pos,
+                        })
+                })
+                .collect();
+            self.index.sort_by_key(|entry| (entry.time, entry.seq));
+            self.evicted = vec![0; self.runner_idxs.len()];
+            self.index_stale = false;
+            self.index_has_evicted = false;
+        }
+
+        /// Account for the oldest `n_evicted` lines of `runner_idx`'s log
+        /// having been dropped. Keeps the positions in `logs` pointing at
+        /// the same lines as before.
+        pub fn evict(&mut self, runner_idx: usize, n_evicted: usize) {
+            if !self.index_stale
+                && let Some(i) = self.runner_idxs.iter().position(|&r| r == runner_idx)
+            {
+                self.evicted[i] += n_evicted;
+                self.index_has_evicted = true;
+            }
+
+            self.logs.retain_mut(|log| {
+                if log.runner_idx != runner_idx {
+                    true
+                } else if log.log_pos < n_evicted {
+                    false
+                } else {
+                    log.log_pos -= n_evicted;
+                    true
+                }
+            });
+        }
+
+        /// Forget the lines of runner `runner_idx`, which has been removed,
+        /// moving later runners' lines down an index to match. Follow with
+        /// [`ScrollState::set_runner_idxs`].
+        #[cfg(feature = "api")]
+        pub fn remove_runner(&mut self, runner_idx: usize) {
+            self.logs
+                .retain_mut(|log| match log.runner_idx.cmp(&runner_idx) {
+                    std::cmp::Ordering::Less => true,
+                    std::cmp::Ordering::Equal => false,
+                    std::cmp::Ordering::Greater => {
+                        log.runner_idx -= 1;
+                        true
+                    }
+                });
+            self.runner_idxs.retain(|&r| r != runner_idx);
+            for r in &mut self.runner_idxs {
+                if *r > runner_idx {
+                    *r -= 1;
+                }
+            }
+            self.index_stale = true;
+        }
+
+        pub fn update_logs(&mut self, runner_logs: &[VecDeque<Log>]) -> iced::Task<Message> {
             debug_assert!(
                 self.runner_idxs.is_empty()
                     || self.runner_idxs.iter().max().unwrap_or(&0) < &runner_logs.len()
@@ -387,24 +3677,41 @@ mod scroll_state {
                 return iced::Task::none();
             }
 
-            self.logs.clear();
+            // Where to scroll to once the lines have been laid out
+            let mut seek_offset = None;
 
-            let line_height = Self::line_height();
-
-            let mut total_lines = 0;
-            for i in 0..self.runner_idxs.len() {
-                total_lines += runner_logs[self.runner_idxs[i]].len();
+            let line_height = self.line_height();
+            if self.index_stale {
+                self.rebuild_index(runner_logs);
+                if let Some(time) = self.seek.take()
+                    && let Some(viewport) = &mut self.viewport
+                {
+                    let top = self.index.partition_point(|entry| entry.time < time);
+                    let offset = widget::scrollable::AbsoluteOffset {
+                        x: 0.0,
+                        y: top as f32 * line_height,
+                    };
+                    viewport.offset_top = offset;
+                    seek_offset = Some(offset);
+                }
+            } else if self.index_has_evicted {
+                let evicted = &self.evicted;
+                self.index.retain(|entry| entry.pos >= evicted[entry.i]);
+                self.index_has_evicted = false;
             }
 
+            // Without a viewport, every line is drawn
+            let viewport = self.viewport.as_ref().filter(|_| self.virtualized);
+
+            let total_lines = self.index.len();
+            self.total_lines = total_lines;
+            let overscan = self.overscan;
+
             // Number of lines visible in the viewport (rounded up)
             let mut n_visible_lines: usize = total_lines;
-            if let Some(viewport) = &self.viewport {
+            if let Some(viewport) = viewport {
                 let visible_size = viewport.bounds.height;
-                n_visible_lines = unsafe {
-                    (visible_size / line_height)
-                        .ceil()
-                        .to_int_unchecked::<usize>()
-                };
+                n_visible_lines = to_lines((visible_size / line_height).ceil(), total_lines);
             }
 
             let mut n_lines_before;
@@ -417,10 +3724,10 @@ mod scroll_state {
                     // Number of lines cut off by the bottom of the viewport
                     // (accuracy for big logs most important at the bottom)
                     n_lines_after = 0;
-                    if let Some(viewport) = &self.viewport {
+                    if let Some(viewport) = viewport {
                         let offset_bottom = viewport.offset_bottom.y;
                         n_lines_after =
-                            unsafe { (offset_bottom / line_height).floor().to_int_unchecked() };
+                            to_lines((offset_bottom / line_height).floor(), total_lines);
                     }
 
                     // Ensure numbers match with total_lines
@@ -442,19 +3749,19 @@ mod scroll_state {
                     }
                     assert!(n_visible_lines + n_lines_after <= total_lines);
 
-                    // We want about 10 lines above and below the rendered viewport
-                    if n_lines_after >= 10 {
-                        n_lines_after -= 10;
-                        n_visible_lines += 10;
+                    // We want about `overscan` lines above and below the rendered viewport
+                    if n_lines_after >= overscan {
+                        n_lines_after -= overscan;
+                        n_visible_lines += overscan;
                     } else {
                         n_visible_lines += n_lines_after;
                         n_lines_after = 0;
                     }
 
                     n_lines_before = total_lines - n_lines_after - n_visible_lines;
-                    if n_lines_before >= 10 {
-                        n_visible_lines += 10;
-                        n_lines_before -= 10;
+                    if n_lines_before >= overscan {
+                        n_visible_lines += overscan;
+                        n_lines_before -= overscan;
                     } else {
                         n_visible_lines += n_lines_before;
                         n_lines_before = 0;
@@ -465,10 +3772,9 @@ mod scroll_state {
 
                     // Number of lines cut off by top of viewport
                     n_lines_before = 0;
-                    if let Some(viewport) = &self.viewport {
+                    if let Some(viewport) = viewport {
                         let offset_top = viewport.offset_top.y;
-                        n_lines_before =
-                            unsafe { (offset_top / line_height).floor().to_int_unchecked() };
+                        n_lines_before = to_lines((offset_top / line_height).floor(), total_lines);
                     }
 
                     // Ensure numbers match with total_lines
@@ -490,19 +3796,19 @@ mod scroll_state {
                     }
                     assert!(n_visible_lines + n_lines_before <= total_lines);
 
-                    // We want about 10 lines above and below the rendered viewport
-                    if n_lines_before >= 10 {
-                        n_lines_before -= 10;
-                        n_visible_lines += 10;
+                    // We want about `overscan` lines above and below the rendered viewport
+                    if n_lines_before >= overscan {
+                        n_lines_before -= overscan;
+                        n_visible_lines += overscan;
                     } else {
                         n_visible_lines += n_lines_before;
                         n_lines_before = 0;
                     }
 
                     n_lines_after = total_lines - n_lines_before - n_visible_lines;
-                    if n_lines_after >= 10 {
-                        n_visible_lines += 10;
-                        n_lines_after -= 10;
+                    if n_lines_after >= overscan {
+                        n_visible_lines += overscan;
+                        n_lines_after -= overscan;
                     } else {
                         n_visible_lines += n_lines_after;
                         n_lines_after = 0;
@@ -513,202 +3819,76 @@ mod scroll_state {
             //self.height       = (total_lines as f32) * line_height;
             self.space_before = (n_lines_before as f32) * line_height;
             self.space_after = (n_lines_after as f32) * line_height;
+            self.first_line = n_lines_before;
 
-            let lens = self
-                .runner_idxs
+            let window = n_lines_before..n_lines_before + n_visible_lines;
+            self.logs = self.index[window]
                 .iter()
-                .map(|i| runner_logs[*i].len())
-                .collect::<Vec<_>>(); // start at end
-            let mut cursors = self.cursors.clone();
-
-            // If Anchor is START, stored cursors are from log start
-            // If Anchor is END,   stored cursors are from log end
-
-            let mut cursor_total = cursors.iter().sum::<usize>();
-
-            // Logs ordered by (DATE DESC, LOGGER ASC)
-            // e.g. 2025-01-01 3
-            //      2025-01-01 2
-            //      2025-01-01 1
-
-            match self.anchor_y {
-                widget::scrollable::Anchor::End => {
-                    // Zipper merge of logs, ordered by log time
-
-                    // Rewind cursors if they're ahead
-                    // (travelling down the stack)
-                    while cursor_total > n_lines_after {
-                        let mut next: Option<(_, SystemTime)> = None;
-                        for i in (0..self.runner_idxs.len()).rev() {
-                            if cursors[i] == 0 {
-                                continue;
-                            } // cursor at start
-                            let pos = lens[i] - cursors[i];
-                            let log = &runner_logs[self.runner_idxs[i]][pos];
-
-                            match next {
-                                None => {
-                                    next = Some((i, log.0));
-                                }
-                                Some((_, t)) => {
-                                    if log.0 <= t {
-                                        // if times match, prefer lower log idx
-                                        next = Some((i, log.0));
-                                    }
-                                }
-                            }
-                        }
-
-                        match next {
-                            Some((i, _)) => {
-                                cursors[i] -= 1;
-                                cursor_total -= 1;
-                            }
-                            None => break,
-                        }
-                    }
-
-                    // Fill logs based on current cursor positions
-                    // (travelling up the stack)
-                    while self.logs.len() < n_visible_lines {
-                        let mut next: Option<(_, _, SystemTime)> = None;
-                        for i in 0..self.runner_idxs.len() {
-                            if cursors[i] == lens[i] {
-                                continue;
-                            } // container exhausted
-                            let pos = lens[i] - cursors[i] - 1;
-                            let log = &runner_logs[self.runner_idxs[i]][pos];
-
-                            match next {
-                                None => {
-                                    next = Some((i, pos, log.0));
-                                }
-                                Some((_, _, t)) => {
-                                    if log.0 > t {
-                                        // if dates match, prefer lower log idx
-                                        next = Some((i, pos, log.0));
-                                    }
-                                }
-                            }
-                        }
-
-                        match next {
-                            Some((i, pos, _)) => {
-                                // Save this position for next time
-                                if cursor_total == n_lines_after {
-                                    self.cursors.copy_from_slice(&cursors);
-                                }
-
-                                if cursor_total >= n_lines_after {
-                                    self.logs.push(ScrollStateLog {
-                                        runner_idx: self.runner_idxs[i],
-                                        log_pos: pos,
-                                    });
-                                }
-
-                                cursors[i] += 1;
-                                cursor_total += 1;
-                            }
-                            None => break,
-                        }
-                    }
-                    self.logs.reverse();
-                }
-                widget::scrollable::Anchor::Start => {
-                    // Zipper merge of logs, ordered by log time
-
-                    // Rewind cursors if they're ahead
-                    // (travelling up the stack)
-                    while cursor_total > n_lines_before {
-                        let mut next: Option<(_, SystemTime)> = None;
-                        for i in 0..self.runner_idxs.len() {
-                            if cursors[i] == 0 {
-                                continue;
-                            } // cursor at start
-                            let pos = cursors[i] - 1;
-                            let log = &runner_logs[self.runner_idxs[i]][pos];
-
-                            match next {
-                                None => {
-                                    next = Some((i, log.0));
-                                }
-                                Some((_, t)) => {
-                                    if log.0 > t {
-                                        // prefer lower log
-                                        next = Some((i, log.0));
-                                    }
-                                }
-                            }
-                        }
-
-                        match next {
-                            Some((i, _)) => {
-                                cursors[i] -= 1;
-                                cursor_total -= 1;
-                            }
-                            None => break,
-                        }
-                    }
-
-                    // Fill logs based on current cursor positions
-                    // (travelling down the stack)
-                    while self.logs.len() < n_visible_lines {
-                        let mut next: Option<(_, _, SystemTime)> = None;
-                        for i in (0..self.runner_idxs.len()).rev() {
-                            if cursors[i] == lens[i] {
-                                continue;
-                            } // container exhausted
-                            let pos = cursors[i];
-                            let log = &runner_logs[self.runner_idxs[i]][pos];
-
-                            match next {
-                                None => {
-                                    next = Some((i, pos, log.0));
-                                }
-                                Some((_, _, t)) => {
-                                    if log.0 <= t {
-                                        next = Some((i, pos, log.0));
-                                    }
-                                }
-                            }
-                        }
-
-                        match next {
-                            Some((i, pos, _)) => {
-                                // Save this position for next time
-                                if cursor_total == n_lines_before {
-                                    self.cursors.copy_from_slice(&cursors);
-                                }
-
-                                if cursor_total >= n_lines_before {
-                                    self.logs.push(ScrollStateLog {
-                                        runner_idx: self.runner_idxs[i],
-                                        log_pos: pos,
-                                    });
-                                }
+                .map(|entry| self.index_log(entry))
+                .collect();
 
-                                cursors[i] += 1;
-                                cursor_total += 1;
-                            }
-                            None => break,
-                        }
-                    }
-                }
+            match seek_offset {
+                Some(offset) => self.scroll_to(offset),
+                None => iced::Task::none(),
             }
-
-            iced::Task::none()
         }
     }
 
+    /// A line count from viewport math, clamped to `0..=max`. Mid-resize
+    /// viewports can be empty or inverted, so `lines` may be negative or NaN.
+    fn to_lines(lines: f32, max: usize) -> usize {
+        // `as` saturates at the bounds of usize, and takes NaN to 0
+        (lines as usize).min(max)
+    }
+
+    /// All lines from `runner_idxs` passing `filter`, ordered as in the
+    /// pane: by time, with ties going to the earlier runner
+    pub fn merge(
+        runner_logs: &[VecDeque<Log>],
+        runner_idxs: &[usize],
+        filter: &LogFilter,
+    ) -> Vec<ScrollStateLog> {
+        let mut merged = runner_idxs
+            .iter()
+            .flat_map(|&runner_idx| {
+                runner_logs[runner_idx]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, log)| filter.matches(&log.1))
+                    .map(move |(log_pos, log)| {
+                        (
+                            (log.0, log.3),
+                            ScrollStateLog {
+                                runner_idx,
+                                log_pos,
+                            },
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+        merged.sort_by_key(|(stamp, _)| *stamp);
+        merged.into_iter().map(|(_, log)| log).collect()
+    }
+
     #[cfg(test)]
     mod test {
         use super::*;
         use itertools::iproduct;
 
+        #[test]
+        fn rows_grow_with_the_text_size() {
+            let mut scroll_state = ScrollState::new();
+            let line_height = scroll_state.line_height();
+            scroll_state.text_size *= 2.0;
+            assert_eq!(scroll_state.line_height(), 2.0 * line_height);
+        }
+
         #[test]
         fn logs_are_ordered() {
+            /// How many lines are logged before the index is built, with
+            /// the rest added as they arrive
             #[derive(Debug)]
-            enum CursorPos {
+            enum IndexedPos {
                 Start,
                 Middle,
                 End,
@@ -718,58 +3898,53 @@ mod scroll_state {
                 widget::scrollable::Anchor::Start,
                 widget::scrollable::Anchor::End,
             ];
-            let test_cursors = &[CursorPos::Start, CursorPos::Middle, CursorPos::End];
+            let test_indexed = &[IndexedPos::Start, IndexedPos::Middle, IndexedPos::End];
 
-            for (anchor_y, cursor_pos) in iproduct!(test_anchors, test_cursors) {
+            for (anchor_y, indexed_pos) in iproduct!(test_anchors, test_indexed) {
                 let mut scroll_state = ScrollState::new();
                 assert_eq!(scroll_state.logs.len(), 0);
 
-                println!("test: {:?}", (anchor_y, cursor_pos));
+                println!("test: {:?}", (anchor_y, indexed_pos));
 
                 use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
                 let mut rng = StdRng::seed_from_u64(99);
                 let runner_idxs = [0, 1];
                 let logs = (0..1000)
-                    .map(|i| {
-                        (
-                            *runner_idxs.choose(&mut rng).unwrap() as usize,
-                            format!("msg {i}\n"),
-                        )
-                    })
+                    .map(|i| (*runner_idxs.choose(&mut rng).unwrap(), format!("msg {i}\n")))
                     .collect::<Vec<_>>();
+                let n_indexed = match indexed_pos {
+                    IndexedPos::Start => 0,
+                    IndexedPos::Middle => logs.len() / 2,
+                    IndexedPos::End => logs.len(),
+                };
 
-                let mut runner_logs = vec![Vec::new(); runner_idxs.len()];
-                for i in 0..logs.len() {
-                    let log = &logs[i];
-                    runner_logs[log.0].push((SystemTime::now(), IO::Stderr(log.1.clone())));
-                    std::thread::sleep(std::time::Duration::from_millis(1));
+                let mut runner_logs = vec![VecDeque::new(); runner_idxs.len()];
+                let push = |runner_logs: &mut Vec<VecDeque<Log>>, i: usize| {
+                    let (runner_idx, msg) = &logs[i];
+                    runner_logs[*runner_idx].push_back((
+                        SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(i as u64),
+                        IO::Stderr(msg.clone()),
+                        Vec::new(),
+                        i as u64,
+                    ));
+                    runner_logs[*runner_idx].len() - 1
+                };
+                for i in 0..n_indexed {
+                    push(&mut runner_logs, i);
                 }
 
-                let _ = scroll_state.set_runner_idxs(runner_idxs.iter().map(|v| *v));
-
-                scroll_state.anchor_y = *anchor_y;
+                let _ = scroll_state.set_runner_idxs(runner_idxs.iter().copied());
+                // set_runner_idxs pauses updates until its scroll_to task completes
+                let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+                let _ = scroll_state.update_logs(&runner_logs);
 
-                match cursor_pos {
-                    CursorPos::Start => {
-                        for i in 0..scroll_state.cursors.len() {
-                            scroll_state.cursors[i] = 0;
-                        }
-                    }
-                    CursorPos::Middle => {
-                        for i in 0..scroll_state.cursors.len() {
-                            scroll_state.cursors[i] = 0;
-                        }
-                        for i in 0..logs.len() / 2 {
-                            scroll_state.cursors[logs[i].0] += 1;
-                        }
-                    }
-                    CursorPos::End => {
-                        for i in 0..scroll_state.cursors.len() {
-                            scroll_state.cursors[i] = runner_logs[i].len();
-                        }
-                    }
+                for i in n_indexed..logs.len() {
+                    let log_pos = push(&mut runner_logs, i);
+                    let runner_log = &runner_logs[logs[i].0];
+                    scroll_state.line_added(logs[i].0, log_pos, &runner_log[log_pos]);
                 }
 
+                scroll_state.anchor_y = *anchor_y;
                 let _ = scroll_state.update_logs(&runner_logs);
 
                 assert_eq!(scroll_state.logs.len(), 1000);
@@ -784,5 +3959,348 @@ mod scroll_state {
                 }
             }
         }
+
+        #[test]
+        fn evicted_lines_leave_the_index() {
+            let time = |i| SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(i);
+            let mut runner_logs = vec![
+                (0..10)
+                    .map(|i| (time(2 * i), IO::Stdout(format!("a {i}")), Vec::new(), 2 * i))
+                    .collect::<VecDeque<_>>(),
+                (0..10)
+                    .map(|i| {
+                        (
+                            time(2 * i + 1),
+                            IO::Stdout(format!("b {i}")),
+                            Vec::new(),
+                            2 * i + 1,
+                        )
+                    })
+                    .collect::<VecDeque<_>>(),
+            ];
+
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..2);
+            let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+            let _ = scroll_state.update_logs(&runner_logs);
+            assert_eq!(scroll_state.total_lines, 20);
+
+            runner_logs[0].drain(..4);
+            scroll_state.evict(0, 4);
+            runner_logs[0].push_back((time(100), IO::Stdout("a 10".to_string()), Vec::new(), 20));
+            scroll_state.line_added(0, 6, &runner_logs[0][6]);
+            let _ = scroll_state.update_logs(&runner_logs);
+
+            assert_eq!(scroll_state.total_lines, 17);
+            let lines = scroll_state
+                .logs
+                .iter()
+                .map(|log| runner_logs[log.runner_idx][log.log_pos].1.line())
+                .collect::<Vec<_>>();
+            assert_eq!(lines[..4], ["b 0", "b 1", "b 2", "b 3"]);
+            assert_eq!(lines[4..6], ["a 4", "b 4"]);
+            assert_eq!(lines.last(), Some(&"a 10"));
+            assert_eq!(
+                scroll_state.merged(&runner_logs).len(),
+                merge(&runner_logs, &[0, 1], &LogFilter::default()).len()
+            );
+        }
+
+        #[test]
+        fn first_line_matches_the_merged_position() {
+            let runner_logs = (0..2)
+                .map(|r| {
+                    (0..500)
+                        .map(|i| {
+                            (
+                                SystemTime::UNIX_EPOCH
+                                    + std::time::Duration::from_millis(2 * i + r),
+                                IO::Stdout(format!("{r} {i}")),
+                                Vec::new(),
+                                2 * i + r,
+                            )
+                        })
+                        .collect::<VecDeque<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..2);
+            let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+            let line_height = scroll_state.line_height();
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+            scroll_state.viewport = Some(Viewport {
+                offset_top: offset(500.0 * line_height),
+                offset_bottom: offset(480.0 * line_height),
+                bounds: iced::Rectangle::new(
+                    iced::Point::ORIGIN,
+                    iced::Size::new(100.0, 20.0 * line_height),
+                ),
+            });
+            let _ = scroll_state.update_logs(&runner_logs);
+
+            let merged = scroll_state.merged(&runner_logs);
+            assert!(scroll_state.first_line > 0);
+            assert_eq!(
+                scroll_state.space_before,
+                scroll_state.first_line as f32 * line_height
+            );
+            for (n, log) in scroll_state.logs.iter().enumerate() {
+                let expected = &merged[scroll_state.first_line + n];
+                assert_eq!(
+                    (log.runner_idx, log.log_pos),
+                    (expected.runner_idx, expected.log_pos)
+                );
+            }
+        }
+
+        #[test]
+        fn equal_times_are_ordered_by_seq() {
+            // Runner 1 logged first, but both runners' lines share one time
+            let time = SystemTime::UNIX_EPOCH;
+            let line = |r: u64, i: u64| {
+                (
+                    time,
+                    IO::Stdout(format!("{r} {i}")),
+                    Vec::new(),
+                    2 * i + 1 - r,
+                )
+            };
+            let mut runner_logs = vec![
+                (0..5).map(|i| line(0, i)).collect::<VecDeque<_>>(),
+                (0..5).map(|i| line(1, i)).collect::<VecDeque<_>>(),
+            ];
+
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..2);
+            let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+            let _ = scroll_state.update_logs(&runner_logs);
+            for r in [1, 0] {
+                runner_logs[r].push_back(line(r as u64, 5));
+                scroll_state.line_added(r, 5, &runner_logs[r][5]);
+            }
+            let _ = scroll_state.update_logs(&runner_logs);
+
+            let expected = (0..6)
+                .flat_map(|i| [format!("1 {i}"), format!("0 {i}")])
+                .collect::<Vec<_>>();
+            let lines = |logs: &[ScrollStateLog]| {
+                logs.iter()
+                    .map(|log| {
+                        runner_logs[log.runner_idx][log.log_pos]
+                            .1
+                            .line()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(lines(&scroll_state.logs), expected);
+            assert_eq!(
+                lines(&merge(&runner_logs, &[0, 1], &LogFilter::default())),
+                expected
+            );
+        }
+
+        #[test]
+        fn unvirtualized_draws_every_line() {
+            let runner_logs = vec![
+                (0..100)
+                    .map(|i| {
+                        (
+                            SystemTime::now(),
+                            IO::Stdout(format!("{i}")),
+                            Vec::new(),
+                            i as u64,
+                        )
+                    })
+                    .collect::<VecDeque<_>>(),
+            ];
+
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..1);
+            let _ = scroll_state.set_virtualized(false);
+            let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+            let line_height = scroll_state.line_height();
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+            scroll_state.viewport = Some(Viewport {
+                offset_top: offset(50.0 * line_height),
+                offset_bottom: offset(40.0 * line_height),
+                bounds: iced::Rectangle::new(
+                    iced::Point::ORIGIN,
+                    iced::Size::new(100.0, 10.0 * line_height),
+                ),
+            });
+            let _ = scroll_state.update_logs(&runner_logs);
+
+            assert_eq!(scroll_state.logs.len(), 100);
+            assert_eq!(scroll_state.space_before, 0.0);
+            assert_eq!(scroll_state.space_after, 0.0);
+        }
+
+        #[test]
+        fn degenerate_viewports_are_clamped() {
+            let runner_logs = vec![
+                (0..100)
+                    .map(|i| {
+                        (
+                            SystemTime::now(),
+                            IO::Stdout(format!("{i}")),
+                            Vec::new(),
+                            i as u64,
+                        )
+                    })
+                    .collect::<VecDeque<_>>(),
+            ];
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+            let viewports = [
+                (0.0, 0.0, 0.0),
+                (-10.0, -50.0, -50.0),
+                (f32::NAN, f32::NAN, f32::NAN),
+                (1e30, 1e30, 1e30),
+                (f32::INFINITY, 0.0, f32::INFINITY),
+            ];
+
+            for anchor_y in [
+                widget::scrollable::Anchor::Start,
+                widget::scrollable::Anchor::End,
+            ] {
+                for (height, top, bottom) in viewports {
+                    let mut scroll_state = ScrollState::new();
+                    let _ = scroll_state.set_runner_idxs(0..1);
+                    let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+                    scroll_state.anchor_y = anchor_y;
+                    scroll_state.viewport = Some(Viewport {
+                        offset_top: offset(top),
+                        offset_bottom: offset(bottom),
+                        bounds: iced::Rectangle::new(
+                            iced::Point::ORIGIN,
+                            iced::Size::new(100.0, height),
+                        ),
+                    });
+                    let _ = scroll_state.update_logs(&runner_logs);
+
+                    let n_before = scroll_state.space_before / scroll_state.line_height();
+                    let n_after = scroll_state.space_after / scroll_state.line_height();
+                    assert!(n_before >= 0.0 && n_after >= 0.0);
+                    assert_eq!(
+                        n_before as usize + scroll_state.logs.len() + n_after as usize,
+                        100,
+                        "{anchor_y:?} {height} {top} {bottom}"
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn runner_selection_keeps_the_scroll_position() {
+            let time = |i| SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(i);
+            let runner_logs = (0..2)
+                .map(|r| {
+                    (0..100)
+                        .map(|i| {
+                            (
+                                time(2 * i + r),
+                                IO::Stdout(format!("{r} {i}")),
+                                Vec::new(),
+                                2 * i + r,
+                            )
+                        })
+                        .collect::<VecDeque<_>>()
+                })
+                .collect::<Vec<_>>();
+            let line_height = ScrollState::new().line_height();
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..2);
+            let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+            scroll_state.anchor_y = widget::scrollable::Anchor::Start;
+            // Line 51 is the second runner's 25th, at 51ms
+            scroll_state.viewport = Some(Viewport {
+                offset_top: offset(51.0 * line_height),
+                offset_bottom: offset(129.0 * line_height),
+                bounds: iced::Rectangle::new(
+                    iced::Point::ORIGIN,
+                    iced::Size::new(100.0, 20.0 * line_height),
+                ),
+            });
+            let _ = scroll_state.update_logs(&runner_logs);
+
+            // Only the first runner is left, whose next line is its 26th
+            let _ = scroll_state.set_runner_idxs(0..1);
+            let _ = scroll_state.update(Message::UpdateLogs, &runner_logs);
+            assert_eq!(scroll_state.anchor_y, widget::scrollable::Anchor::Start);
+            let viewport = scroll_state.viewport.as_ref().unwrap();
+            assert_eq!(viewport.offset_top.y, 26.0 * line_height);
+            let top = scroll_state.logs[26 - scroll_state.first_line].log_pos;
+            assert_eq!(runner_logs[0][top].1.line(), "0 26");
+
+            // Following along at the end, it jumps to the end as before
+            scroll_state.anchor_y = widget::scrollable::Anchor::End;
+            let _ = scroll_state.set_runner_idxs(0..2);
+            assert!(scroll_state.viewport.is_none());
+        }
+
+        #[test]
+        fn overscan_is_drawn_around_the_viewport() {
+            let runner_logs = vec![
+                (0..100)
+                    .map(|i| {
+                        (
+                            SystemTime::now(),
+                            IO::Stdout(format!("{i}")),
+                            Vec::new(),
+                            i as u64,
+                        )
+                    })
+                    .collect::<VecDeque<_>>(),
+            ];
+            let line_height = ScrollState::new().line_height();
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+
+            for (overscan, bottom, n_drawn, n_after) in [
+                (10, 50.0, 30, 40),
+                (0, 50.0, 10, 50),
+                (25, 50.0, 60, 25),
+                (25, 5.0, 40, 0),
+            ] {
+                let mut scroll_state = ScrollState::new();
+                scroll_state.overscan = overscan;
+                let _ = scroll_state.set_runner_idxs(0..1);
+                let _ = scroll_state.update(Message::SetEnableUpdates(true), &runner_logs);
+                scroll_state.viewport = Some(Viewport {
+                    offset_top: offset((90.0 - bottom) * line_height),
+                    offset_bottom: offset(bottom * line_height),
+                    bounds: iced::Rectangle::new(
+                        iced::Point::ORIGIN,
+                        iced::Size::new(100.0, 10.0 * line_height),
+                    ),
+                });
+                let _ = scroll_state.update_logs(&runner_logs);
+
+                assert_eq!(scroll_state.logs.len(), n_drawn, "overscan {overscan}");
+                assert_eq!(scroll_state.space_after, n_after as f32 * line_height);
+            }
+        }
+
+        #[test]
+        fn follow_sets_the_anchor() {
+            let runner_logs = vec![VecDeque::new()];
+            let mut scroll_state = ScrollState::new();
+            let _ = scroll_state.set_runner_idxs(0..1);
+            let offset = |y| widget::scrollable::AbsoluteOffset { x: 0.0, y };
+            scroll_state.viewport = Some(Viewport {
+                offset_top: offset(50.0),
+                offset_bottom: offset(0.0),
+                bounds: iced::Rectangle::new(iced::Point::ORIGIN, iced::Size::new(100.0, 100.0)),
+            });
+
+            let _ = scroll_state.update(Message::SetFollow(Follow::Never), &runner_logs);
+            assert_eq!(scroll_state.anchor_y, widget::scrollable::Anchor::Start);
+
+            let _ = scroll_state.update(Message::SetFollow(Follow::Always), &runner_logs);
+            assert_eq!(scroll_state.anchor_y, widget::scrollable::Anchor::End);
+            assert_eq!(scroll_state.follow(), Follow::Always);
+        }
     }
 }
@@ -0,0 +1,1531 @@
+use std::path::{Path, PathBuf};
+
+// Unknown fields are refused, so a typo can't silently do nothing
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Other config files to merge runners from, relative to this file.
+    /// Runners defined here replace included runners with the same name,
+    /// and later includes replace earlier ones.
+    #[serde(default)]
+    pub include: Vec<String>,
+    pub runners: Vec<RunnerConfig>,
+    /// Tint log rows by the log level they start with
+    #[serde(default)]
+    pub level_colors: LevelColors,
+    /// Show a desktop notification when a runner exits with an error
+    #[serde(default)]
+    pub notify_on_failure: bool,
+    /// Serve the HTTP API here, e.g. `127.0.0.1:7878`.
+    /// Needs battlestation built with the `api` feature.
+    pub api_addr: Option<std::net::SocketAddr>,
+    /// Default `max_log_lines` for runners that don't set their own
+    pub max_log_lines: Option<usize>,
+    /// Lines drawn beyond each edge of the log pane. More avoids gaps
+    /// when scrolling quickly, at the cost of drawing more.
+    pub log_overscan: Option<usize>,
+    /// Remember which runners show logs, restart policies, the tag filter,
+    /// follow mode and window geometry between launches, in a session
+    /// file next to this config, e.g. `battlestation.toml.session.json`
+    #[serde(default)]
+    pub save_session: bool,
+    /// One of iced's built-in themes, e.g. `Dark`, `Dracula` or
+    /// `Tokyo Night`. Case, spaces and underscores don't matter.
+    #[serde(default, deserialize_with = "deserialize_theme")]
+    pub theme: Option<iced::Theme>,
+    /// Font family for logs and other monospace text, e.g.
+    /// `JetBrains Mono`. It must be installed, or loaded with `font_file`.
+    pub font: Option<String>,
+    /// A font file to load at startup, relative to this config
+    pub font_file: Option<String>,
+    /// Base text size, in logical pixels. Defaults to 16.
+    pub text_size: Option<f32>,
+    /// Drawn between a log row's runner name and its line, by stream
+    #[serde(default)]
+    pub glyphs: Glyphs,
+    /// A hex color for stderr rows that have no level color
+    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    pub stderr_color: Option<iced::Color>,
+    /// Columns between tab stops in log lines, so tabbed columns line up.
+    /// Defaults to 8, and 0 leaves tabs as they are.
+    pub tab_width: Option<usize>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Glyphs {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Default for Glyphs {
+    fn default() -> Glyphs {
+        Glyphs {
+            stdout: crate::app::GLYPH_STDOUT.to_string(),
+            stderr: crate::app::GLYPH_STDERR.to_string(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LevelColors {
+    /// Off by default
+    pub enabled: bool,
+    /// Checked in order, so put longer tokens sharing a prefix first
+    pub levels: Vec<Level>,
+}
+
+impl Default for LevelColors {
+    fn default() -> LevelColors {
+        let level = |token: &str, color| Level {
+            token: token.to_string(),
+            color: iced::Color::parse(color).unwrap(),
+        };
+        LevelColors {
+            enabled: false,
+            levels: vec![
+                level("ERROR", "#f14c4c"),
+                level("WARN", "#e5c07b"),
+                level("INFO", "#61afef"),
+                level("DEBUG", "#808080"),
+            ],
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    /// The word a line starts with, e.g. `ERROR`
+    pub token: String,
+    /// A hex color, e.g. `#ff0000`
+    #[serde(deserialize_with = "deserialize_color")]
+    pub color: iced::Color,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunnerConfig {
+    pub name: String,
+    /// Run with `bash -c`, or `cmd /C` on Windows
+    #[serde(default)]
+    pub script: String,
+    /// A file to read the `script` from when the config is loaded,
+    /// relative to the config
+    pub script_file: Option<String>,
+    /// A program and its arguments, run directly instead of a `script`.
+    /// Without a shell in between, the program itself gets the signals
+    /// we forward and leads the process group that's cleaned up when it
+    /// exits. A shell may not pass signals on, or may exit first.
+    pub command: Option<Vec<String>>,
+    /// Shown under the runner's name in the UI
+    pub description: Option<String>,
+    /// Labels for filtering runners in the UI
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Run as soon as the UI opens
+    #[serde(default)]
+    pub auto_start: bool,
+    /// Names of runners that must be running, or have completed
+    /// successfully, before this runner starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Wait this long after a run is requested before spawning
+    pub start_delay_ms: Option<u64>,
+    /// When to run again after completing: never, always or on_failure
+    #[serde(default)]
+    pub restart: crate::runner::RestartPolicy,
+    /// Restart delay after the first consecutive failure, doubling for each
+    /// further failure. Defaults to 1000.
+    pub backoff_base_ms: Option<u64>,
+    /// Longest restart delay after repeated failures. Defaults to 60000.
+    pub backoff_cap_ms: Option<u64>,
+    /// Stop restarting automatically after this many restarts in a row
+    pub max_restarts: Option<u32>,
+    /// How long a stopped script gets between SIGTERM and SIGKILL.
+    /// Defaults to 5000.
+    pub kill_timeout_ms: Option<u64>,
+    /// Run the script in a new session, without a controlling terminal,
+    /// so it's unaffected by hangups. Unix only.
+    #[serde(default)]
+    pub new_session: bool,
+    /// Environment variables set for the script
+    #[serde(default)]
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Start the script with only the variables in `env`, rather than
+    /// adding them to ours. `PATH` isn't kept either, so set it in `env`
+    /// or give programs by their full paths.
+    #[serde(default)]
+    pub env_clear: bool,
+    /// Command run every few seconds while running. Exiting with 0 means
+    /// the runner is healthy.
+    pub health_check: Option<String>,
+    /// Regex matched against stdout lines. Runners depending on this one
+    /// wait for a matching line, not just for it to start.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub ready_when: Option<regex::Regex>,
+    /// How long the output lights stay lit after output. Defaults to 100.
+    pub activity_on_ms: Option<u64>,
+    /// How long the output lights stay dark before lighting again.
+    /// Defaults to 50.
+    pub activity_off_ms: Option<u64>,
+    /// Bytes read from the script's stdout or stderr at a time. Bigger
+    /// buffers suit very chatty scripts. Defaults to 1024.
+    pub read_buffer_size: Option<usize>,
+    /// Encoding of the script's output, e.g. `latin1` or `shift_jis`.
+    /// Any label from the WHATWG Encoding Standard. Defaults to UTF-8.
+    pub encoding: Option<String>,
+    /// Longest line logged, in bytes. Longer lines are broken up, so
+    /// output that never ends a line can't use up all our memory.
+    /// Defaults to 65536.
+    pub max_line_len: Option<usize>,
+    /// Keep at most this many log lines, dropping the oldest. Each line
+    /// costs its text plus about a hundred bytes, so a chatty runner kept
+    /// for hours is the usual reason memory grows. Defaults to the
+    /// config's `max_log_lines`, or to keeping every line if that isn't
+    /// set either.
+    pub max_log_lines: Option<usize>,
+    /// Show logs as plain text instead of rendering ANSI colors. Escape
+    /// codes are removed either way.
+    #[serde(default)]
+    pub strip_ansi: bool,
+    /// Count a line that repeats the one before it on that line's row,
+    /// instead of logging it again, e.g. for retry loops
+    #[serde(default)]
+    pub collapse_repeats: bool,
+    /// Show a desktop notification with the exit status and runtime
+    /// whenever the script exits, e.g. for long one-off tasks
+    #[serde(default)]
+    pub notify_on_complete: bool,
+    /// Ask before the stop button kills the script, for runs that are
+    /// expensive to interrupt
+    #[serde(default)]
+    pub confirm_kill: bool,
+    /// Files or directories to watch, relative to the config. The runner
+    /// is restarted when anything in them changes.
+    #[serde(default)]
+    pub watch: Vec<PathBuf>,
+    /// Also start the runner on changes while it isn't running
+    #[serde(default)]
+    pub watch_always: bool,
+    /// How long changes must settle before restarting, so one save
+    /// restarts once. Defaults to 500.
+    pub watch_debounce_ms: Option<u64>,
+    /// Stop runs that go on longer than this, the same way the stop
+    /// button does
+    pub timeout_ms: Option<u64>,
+    /// Run on a schedule: every so often, e.g. `{ every = "30s" }`, or
+    /// whenever a cron expression matches, e.g. `{ cron = "0 */15 * * * *" }`.
+    /// Cron expressions start with a seconds field. A run is skipped if
+    /// the last one is still going.
+    #[serde(default, deserialize_with = "deserialize_schedule")]
+    pub schedule: Option<crate::runner::Schedule>,
+    /// Mark the runner as stalled when a run has no output for this long,
+    /// for scripts that are quiet only when they're stuck
+    pub stall_timeout_ms: Option<u64>,
+    /// Show a desktop notification when a run stalls
+    #[serde(default)]
+    pub notify_on_stall: bool,
+    /// A hex color for the runner's name on its log lines, e.g. `#4e79a7`.
+    /// Runners without one get a color from a palette.
+    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    pub color: Option<iced::Color>,
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<regex::Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| regex::Regex::new(&pattern).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_schedule<'de, D>(
+    deserializer: D,
+) -> Result<Option<crate::runner::Schedule>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use crate::runner::Schedule;
+    use serde::Deserialize;
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum ScheduleConfig {
+        Every(String),
+        Cron(String),
+    }
+
+    Option::<ScheduleConfig>::deserialize(deserializer)?
+        .map(|schedule| match schedule {
+            ScheduleConfig::Every(interval) => parse_duration(&interval)
+                .filter(|interval| !interval.is_zero())
+                .map(Schedule::Every)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid interval: {interval}"))),
+            ScheduleConfig::Cron(expression) => expression
+                .parse::<cron::Schedule>()
+                .map(|schedule| Schedule::Cron(Box::new(schedule)))
+                .map_err(|err| {
+                    serde::de::Error::custom(format!(
+                        "invalid cron expression {expression:?}: {err}"
+                    ))
+                }),
+        })
+        .transpose()
+}
+
+/// A duration like `500ms`, `30s` or `1h30m`. Units are ms, s, m, h and d.
+fn parse_duration(duration: &str) -> Option<std::time::Duration> {
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = std::time::Duration::ZERO;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (count, after) = rest.split_at(digits);
+        let count: u32 = count.parse().ok()?;
+        let letters = after
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(letters);
+        let unit = match unit.trim() {
+            "ms" => std::time::Duration::from_millis(1),
+            "s" => std::time::Duration::from_secs(1),
+            "m" => std::time::Duration::from_secs(60),
+            "h" => std::time::Duration::from_secs(60 * 60),
+            "d" => std::time::Duration::from_secs(24 * 60 * 60),
+            _ => return None,
+        };
+        total = total.checked_add(unit.checked_mul(count)?)?;
+        rest = after;
+    }
+    Some(total)
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<iced::Color, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    let color = String::deserialize(deserializer)?;
+    iced::Color::parse(&color)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid color: {color}")))
+}
+
+fn deserialize_optional_color<'de, D>(deserializer: D) -> Result<Option<iced::Color>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    Option::<String>::deserialize(deserializer)?
+        .map(|color| {
+            iced::Color::parse(&color)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid color: {color}")))
+        })
+        .transpose()
+}
+
+fn deserialize_theme<'de, D>(deserializer: D) -> Result<Option<iced::Theme>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    Option::<String>::deserialize(deserializer)?
+        .map(|name| {
+            iced::Theme::ALL
+                .iter()
+                .find(|theme| normalize(&theme.to_string()) == normalize(&name))
+                .cloned()
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown theme: {name}")))
+        })
+        .transpose()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Empty(PathBuf),
+    Parse(PathBuf, String),
+    /// Fields we don't know, likely typos, each with the runner it's on
+    /// if it's on one
+    UnknownFields {
+        path: PathBuf,
+        fields: Vec<(String, Option<String>)>,
+        error: String,
+    },
+    IncludeCycle(Vec<PathBuf>),
+    ExclusiveFields {
+        runner: String,
+        fields: [&'static str; 2],
+    },
+    /// Neither `script`, `script_file` nor `command` was given
+    MissingScript {
+        runner: String,
+    },
+    /// `command` has no program to run
+    EmptyCommand {
+        runner: String,
+    },
+    DuplicateName {
+        name: String,
+        first: usize,
+        duplicate: usize,
+    },
+    UnknownDependency {
+        runner: String,
+        dependency: String,
+    },
+    DependencyCycle(Vec<String>),
+}
+
+/// Config path meaning "read the whole config from stdin"
+pub const STDIN_PATH: &str = "-";
+
+/// Human readable name for a config path, e.g. for error messages
+pub fn display_path(path: &Path) -> String {
+    if path == Path::new(STDIN_PATH) {
+        "stdin".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, e) => {
+                write!(f, "Error opening config file {}: {e}", display_path(path))
+            }
+            Error::Empty(path) => write!(f, "Config from {} is empty", display_path(path)),
+            Error::Parse(path, e) => write!(f, "Error parsing {}: {e}", display_path(path)),
+            Error::UnknownFields {
+                path,
+                fields,
+                error,
+            } => {
+                let plural = if fields.len() == 1 { "" } else { "s" };
+                let fields = fields
+                    .iter()
+                    .map(|(field, runner)| match runner {
+                        Some(runner) => format!("`{field}` on runner {runner:?}"),
+                        None => format!("`{field}`"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "Unknown field{plural} {fields} in {}\n{error}",
+                    display_path(path)
+                )
+            }
+            Error::IncludeCycle(paths) => {
+                let paths = paths
+                    .iter()
+                    .map(|path| display_path(path))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                write!(f, "Config include cycle: {paths}")
+            }
+            Error::ExclusiveFields {
+                runner,
+                fields: [a, b],
+            } => write!(f, "Runner {runner:?} can't set both {a} and {b}"),
+            Error::MissingScript { runner } => write!(
+                f,
+                "Runner {runner:?} needs a script, script_file or command"
+            ),
+            Error::EmptyCommand { runner } => {
+                write!(f, "Runner {runner:?} has a command with no program")
+            }
+            Error::DuplicateName {
+                name,
+                first,
+                duplicate,
+            } => {
+                write!(
+                    f,
+                    "Runner name {name:?} is used by both runners[{first}] and runners[{duplicate}]"
+                )
+            }
+            Error::UnknownDependency { runner, dependency } => {
+                write!(
+                    f,
+                    "Runner {runner:?} depends on unknown runner {dependency:?}"
+                )
+            }
+            Error::DependencyCycle(names) => {
+                write!(f, "Runner dependency cycle: {}", names.join(" -> "))
+            }
+        }
+    }
+}
+
+/// Extensions searched for when discovering a default config, in order
+const EXTENSIONS: &[&str] = &["json", "toml", "yaml"];
+
+impl Format {
+    /// Guess the format from the file extension, defaulting to JSON
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Format::Toml,
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Json,
+        }
+    }
+}
+
+pub fn parse(contents: &str, format: Format) -> Result<Config, String> {
+    match format {
+        Format::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+    }
+}
+
+/// A single runner given as JSON, e.g. to add while running. Its paths
+/// are relative to `base_dir`, the loaded config's directory, as they would
+/// be in the config, but config-wide defaults don't apply.
+#[cfg(feature = "api")]
+pub fn parse_runner(contents: &str, base_dir: &Path) -> Result<RunnerConfig, String> {
+    let mut runner: RunnerConfig = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    read_script_files(std::slice::from_mut(&mut runner), base_dir).map_err(|e| e.to_string())?;
+    check_script(&runner).map_err(|e| e.to_string())?;
+    Ok(runner)
+}
+
+/// Load a config from `path`, or from stdin if `path` is [`STDIN_PATH`],
+/// merging in the runners of any included configs.
+/// Configs from stdin are parsed as JSON.
+pub fn load_config(path: &Path) -> Result<Config, Error> {
+    let mut stack = Vec::new();
+    if let Ok(canonical) = path.canonicalize() {
+        stack.push(canonical);
+    }
+    let mut config = load_with_includes(path, &mut stack)?;
+    apply_defaults(&mut config);
+
+    check_names(&config)?;
+    check_scripts(&config)?;
+    check_dependencies(&config)?;
+
+    Ok(config)
+}
+
+/// Fill in runner settings left unset with the config's defaults, which
+/// apply to included runners too
+fn apply_defaults(config: &mut Config) {
+    for runner in &mut config.runners {
+        runner.max_log_lines = runner.max_log_lines.or(config.max_log_lines);
+    }
+}
+
+/// Ensure no two runners share a name, as runners are found by name
+fn check_names(config: &Config) -> Result<(), Error> {
+    for (duplicate, runner) in config.runners.iter().enumerate() {
+        if let Some(first) = config.runners[..duplicate]
+            .iter()
+            .position(|r| r.name == runner.name)
+        {
+            return Err(Error::DuplicateName {
+                name: runner.name.clone(),
+                first,
+                duplicate,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Ensure each runner gives its script only one way
+fn check_scripts(config: &Config) -> Result<(), Error> {
+    config.runners.iter().try_for_each(check_script)
+}
+
+fn check_script(runner: &RunnerConfig) -> Result<(), Error> {
+    let runner_name = || runner.name.clone();
+    match &runner.command {
+        Some(_) if !runner.script.is_empty() => Err(Error::ExclusiveFields {
+            runner: runner_name(),
+            fields: ["script", "command"],
+        }),
+        Some(command) if command.first().is_none_or(|program| program.is_empty()) => {
+            Err(Error::EmptyCommand {
+                runner: runner_name(),
+            })
+        }
+        Some(_) => Ok(()),
+        // Without either, it would run an empty script
+        None if runner.script.is_empty() => Err(Error::MissingScript {
+            runner: runner_name(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Ensure every `depends_on` names a runner, and that there are no cycles
+fn check_dependencies(config: &Config) -> Result<(), Error> {
+    let index_of = |name: &str| config.runners.iter().position(|r| r.name == name);
+
+    let mut dependencies = Vec::with_capacity(config.runners.len());
+    for runner in &config.runners {
+        let mut idxs = Vec::with_capacity(runner.depends_on.len());
+        for dependency in &runner.depends_on {
+            match index_of(dependency) {
+                Some(i) => idxs.push(i),
+                None => {
+                    return Err(Error::UnknownDependency {
+                        runner: runner.name.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        }
+        dependencies.push(idxs);
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        InProgress,
+        Done,
+    }
+
+    // Depth first search, with the current path kept in `stack`
+    fn visit(
+        i: usize,
+        dependencies: &[Vec<usize>],
+        visits: &mut [Visit],
+        stack: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        match visits[i] {
+            Visit::Done => return Ok(()),
+            Visit::InProgress => {
+                let start = stack.iter().position(|&j| j == i).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(i);
+                return Err(cycle);
+            }
+            Visit::New => (),
+        }
+
+        visits[i] = Visit::InProgress;
+        stack.push(i);
+        for &j in &dependencies[i] {
+            visit(j, dependencies, visits, stack)?;
+        }
+        stack.pop();
+        visits[i] = Visit::Done;
+
+        Ok(())
+    }
+
+    let mut visits = vec![Visit::New; config.runners.len()];
+    for i in 0..config.runners.len() {
+        if let Err(cycle) = visit(i, &dependencies, &mut visits, &mut Vec::new()) {
+            let names = cycle
+                .into_iter()
+                .map(|j| config.runners[j].name.clone())
+                .collect();
+            return Err(Error::DependencyCycle(names));
+        }
+    }
+
+    Ok(())
+}
+
+/// The directory paths in the config at `path` are relative to. For
+/// configs from stdin, that's the working directory.
+pub fn base_dir(path: &Path) -> &Path {
+    if path == Path::new(STDIN_PATH) {
+        Path::new(".")
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    }
+}
+
+fn load_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Config, Error> {
+    let mut config = read_config(path)?;
+
+    let base_dir = base_dir(path);
+
+    let mut runners = Vec::new();
+    for include in &config.include {
+        let include_path = base_dir.join(include);
+        let canonical = include_path
+            .canonicalize()
+            .map_err(|e| Error::Io(include_path.clone(), e))?;
+
+        if stack.contains(&canonical) {
+            let mut cycle = stack.clone();
+            cycle.push(canonical);
+            return Err(Error::IncludeCycle(cycle));
+        }
+
+        stack.push(canonical);
+        let included = load_with_includes(&include_path, stack)?;
+        stack.pop();
+
+        merge_runners(&mut runners, included.runners);
+    }
+    read_script_files(&mut config.runners, base_dir)?;
+    for runner in &mut config.runners {
+        for path in &mut runner.watch {
+            *path = base_dir.join(&*path);
+        }
+    }
+    merge_runners(&mut runners, std::mem::take(&mut config.runners));
+    config.runners = runners;
+
+    Ok(config)
+}
+
+/// Fill in the `script` of runners with a `script_file`, relative to
+/// `base_dir`
+fn read_script_files(runners: &mut [RunnerConfig], base_dir: &Path) -> Result<(), Error> {
+    for runner in runners {
+        let Some(script_file) = &runner.script_file else {
+            continue;
+        };
+        let other = if !runner.script.is_empty() {
+            Some("script")
+        } else if runner.command.is_some() {
+            Some("command")
+        } else {
+            None
+        };
+        if let Some(other) = other {
+            return Err(Error::ExclusiveFields {
+                runner: runner.name.clone(),
+                fields: [other, "script_file"],
+            });
+        }
+
+        let path = base_dir.join(script_file);
+        runner.script = std::fs::read_to_string(&path).map_err(|e| Error::Io(path, e))?;
+    }
+    Ok(())
+}
+
+/// Add `runners` to `base`, replacing any runner in `base` with the same
+/// name. Duplicates within `runners` itself are kept for [`check_names`].
+fn merge_runners(base: &mut Vec<RunnerConfig>, runners: Vec<RunnerConfig>) {
+    let n_base = base.len();
+    for runner in runners {
+        match base[..n_base].iter().position(|r| r.name == runner.name) {
+            Some(i) => base[i] = runner,
+            None => base.push(runner),
+        }
+    }
+}
+
+fn read_config(path: &Path) -> Result<Config, Error> {
+    let contents = if path == Path::new(STDIN_PATH) {
+        use std::io::Read;
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        contents
+    } else {
+        std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?
+    };
+
+    if contents.trim().is_empty() {
+        return Err(Error::Empty(path.to_path_buf()));
+    }
+
+    let format = Format::from_path(path);
+    parse(&contents, format).map_err(|e| {
+        let fields = find_unknown_fields(&contents, format);
+        if fields.is_empty() {
+            Error::Parse(path.to_path_buf(), e)
+        } else {
+            Error::UnknownFields {
+                path: path.to_path_buf(),
+                fields,
+                error: e,
+            }
+        }
+    })
+}
+
+/// Every field in `contents` that isn't one of ours, with the name of the
+/// runner it's on if it's on one. Found by parsing `contents` again
+/// without checking any fields and looking where each key sits, rather
+/// than trusting the wording of serde's error.
+fn find_unknown_fields(contents: &str, format: Format) -> Vec<(String, Option<String>)> {
+    fn array(value: Option<&serde_json::Value>) -> &[serde_json::Value] {
+        value
+            .and_then(|value| value.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    let config: Option<serde_json::Value> = match format {
+        Format::Json => serde_json::from_str(contents).ok(),
+        Format::Toml => toml::from_str::<toml::Value>(contents)
+            .ok()
+            .and_then(|config| serde_json::to_value(config).ok()),
+        Format::Yaml => serde_yaml::from_str::<serde_yaml::Value>(contents)
+            .ok()
+            .and_then(|config| serde_json::to_value(config).ok()),
+    };
+    let Some(config) = config else {
+        return Vec::new();
+    };
+    let mut sections = vec![(&config, fields_of::<Config>())];
+    if let Some(glyphs) = config.get("glyphs") {
+        sections.push((glyphs, fields_of::<Glyphs>()));
+    }
+    if let Some(level_colors) = config.get("level_colors") {
+        sections.push((level_colors, fields_of::<LevelColors>()));
+        for level in array(level_colors.get("levels")) {
+            sections.push((level, fields_of::<Level>()));
+        }
+    }
+    let mut unknown = Vec::new();
+    for (value, fields) in sections {
+        unknown.extend(unknown_keys(value, fields).map(|field| (field, None)));
+    }
+    for runner in array(config.get("runners")) {
+        let name = runner.get("name").and_then(|name| name.as_str());
+        unknown.extend(
+            unknown_keys(runner, fields_of::<RunnerConfig>())
+                .map(|field| (field, name.map(str::to_string))),
+        );
+    }
+    unknown
+}
+
+/// Keys of `value`, if it's an object, that aren't among `fields`
+fn unknown_keys(
+    value: &serde_json::Value,
+    fields: &'static [&'static str],
+) -> impl Iterator<Item = String> {
+    value
+        .as_object()
+        .into_iter()
+        .flat_map(|object| object.keys())
+        .filter(move |key| !fields.contains(&key.as_str()))
+        .cloned()
+}
+
+/// The fields a derived `T` is deserialized from, which it hands to the
+/// deserializer when asking for a struct
+fn fields_of<T: serde::de::DeserializeOwned>() -> &'static [&'static str] {
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for Fields<'_> {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V>(self, _: V) -> Result<V::Value, Self::Error>
+        where
+            V: serde::de::Visitor<'de>,
+        {
+            Err(serde::de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: serde::de::Visitor<'de>,
+        {
+            *self.0 = fields;
+            Err(serde::de::Error::custom("only the fields were wanted"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
+
+/// Check a loaded config for problems that deserialization doesn't catch
+pub fn validate(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (i, runner) in config.runners.iter().enumerate() {
+        match &runner.command {
+            Some(command) if command.first().is_none_or(|program| program.is_empty()) => {
+                problems.push(format!(
+                    "runners[{i}] ({:?}): command is empty",
+                    runner.name
+                ));
+            }
+            Some(_) => (),
+            None if runner.script.trim().is_empty() => {
+                problems.push(format!("runners[{i}] ({:?}): script is empty", runner.name));
+            }
+            None => (),
+        }
+
+        if runner.read_buffer_size == Some(0) {
+            problems.push(format!(
+                "runners[{i}] ({:?}): read_buffer_size must be at least 1",
+                runner.name
+            ));
+        }
+
+        if let Some(encoding) = &runner.encoding
+            && encoding_rs::Encoding::for_label(encoding.as_bytes()).is_none()
+        {
+            problems.push(format!(
+                "runners[{i}] ({:?}): unknown encoding {encoding:?}",
+                runner.name
+            ));
+        }
+        if runner.max_line_len == Some(0) {
+            problems.push(format!(
+                "runners[{i}] ({:?}): max_line_len must be at least 1",
+                runner.name
+            ));
+        }
+    }
+
+    if let Some(text_size) = config.text_size
+        && !(text_size.is_finite() && text_size > 0.0)
+    {
+        problems.push(format!("text_size must be positive, not {text_size}"));
+    }
+
+    problems
+}
+
+/// Directories searched for a default config:
+/// `$XDG_CONFIG_HOME/battlestation`, then `~/.config/battlestation`
+pub fn default_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(xdg_config_home) = std::env::var_os("XDG_CONFIG_HOME")
+        && !xdg_config_home.is_empty()
+    {
+        dirs.push(PathBuf::from(xdg_config_home).join("battlestation"));
+    }
+
+    if let Some(home) = std::env::var_os("HOME")
+        && !home.is_empty()
+    {
+        let dir = PathBuf::from(home).join(".config").join("battlestation");
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    dirs
+}
+
+/// Find the first `config.{json,toml,yaml}` in the default directories
+pub fn find_default() -> Option<PathBuf> {
+    default_dirs()
+        .into_iter()
+        .flat_map(|dir| {
+            EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("config.{ext}")))
+        })
+        .find(|path| path.is_file())
+}
+
+/// A TOML config with a runner for each executable `*.sh` file in `dir`,
+/// named after the file and reading it as its `script_file`. Hidden
+/// files are skipped. Paths are relative to `config_dir`, where the
+/// config is to be saved, as `script_file` is read relative to that.
+pub fn import_scripts(dir: &Path, config_dir: &Path) -> std::io::Result<String> {
+    #[derive(serde::Serialize)]
+    struct Imported {
+        runners: Vec<ImportedRunner>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct ImportedRunner {
+        name: String,
+        script_file: String,
+    }
+
+    let dir = std::fs::canonicalize(dir)?;
+    let config_dir = std::fs::canonicalize(config_dir)?;
+    let mut runners = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Some(name) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|stem| !stem.starts_with('.'))
+        else {
+            continue;
+        };
+        if path.extension().is_none_or(|ext| ext != "sh") || !is_executable(&entry.metadata()?) {
+            continue;
+        }
+        runners.push(ImportedRunner {
+            name: name.to_string(),
+            script_file: relative_path(&path, &config_dir).display().to_string(),
+        });
+    }
+    runners.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(toml::to_string(&Imported { runners }).unwrap())
+}
+
+/// `path` relative to `base`, both absolute, or `path` itself if they
+/// share no root, e.g. being on different Windows drives
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_path_buf();
+    }
+    base.components()
+        .skip(common)
+        .map(|_| std::path::Component::ParentDir)
+        .chain(path.components().skip(common))
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+/// Windows has no executable bit, so any script will do
+#[cfg(not(unix))]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    metadata.is_file()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formats_parse_the_same() {
+        let json = r#"{ "runners": [{ "name": "a", "script": "echo a" }] }"#;
+        let toml = "[[runners]]\nname = \"a\"\nscript = \"echo a\"\n";
+        let yaml = "runners:\n  - name: a\n    script: echo a\n";
+
+        for (contents, format) in [
+            (json, Format::Json),
+            (toml, Format::Toml),
+            (yaml, Format::Yaml),
+        ] {
+            let config = parse(contents, format).unwrap();
+            assert_eq!(config.runners.len(), 1);
+            assert_eq!(config.runners[0].name, "a");
+            assert_eq!(config.runners[0].script, "echo a");
+        }
+
+        assert_eq!(Format::from_path(Path::new("a/config.yml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("a/config")), Format::Json);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn executable_scripts_are_imported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("battlestation-import-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, mode) in [
+            ("web.sh", 0o755),
+            ("db.sh", 0o700),
+            ("notes.sh", 0o644),
+            (".hidden.sh", 0o755),
+            ("build.py", 0o755),
+        ] {
+            let path = dir.join(file);
+            std::fs::write(&path, "echo\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let config = parse(&import_scripts(&dir, &dir).unwrap(), Format::Toml).unwrap();
+        let runners = config
+            .runners
+            .iter()
+            .map(|runner| (runner.name.as_str(), runner.script_file.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            runners,
+            [("db", "db.sh".to_string()), ("web", "web.sh".to_string())]
+        );
+
+        // Saved elsewhere, the config still finds the scripts
+        let config_dir = dir.join("configs/dev");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        let config_path = config_dir.join("battlestation.toml");
+        std::fs::write(&config_path, import_scripts(&dir, &config_dir).unwrap()).unwrap();
+        let config = load_config(&config_path).unwrap();
+        assert_eq!(
+            config.runners[0].script_file.as_deref(),
+            Some("../../db.sh")
+        );
+        assert_eq!(config.runners[0].script, "echo\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_fields_are_refused() {
+        let dir = std::env::temp_dir().join(format!(
+            "battlestation-unknown-field-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let unknown_fields = |path: &Path, contents: &str| {
+            std::fs::write(path, contents).unwrap();
+            match load_config(path) {
+                Err(Error::UnknownFields { fields, .. }) => fields,
+                _ => panic!("expected unknown fields"),
+            }
+        };
+        let field =
+            |field: &str, runner: Option<&str>| (field.to_string(), runner.map(str::to_string));
+
+        let path = dir.join("config.toml");
+        assert_eq!(
+            unknown_fields(
+                &path,
+                "[[runners]]\nname = \"a\"\n\n[[runners]]\nname = \"web\"\nscrpit = \"x\"\n",
+            ),
+            [field("scrpit", Some("web"))]
+        );
+        // A runner having a key of the same name doesn't put it on them
+        assert_eq!(
+            unknown_fields(
+                &path,
+                "script = \"x\"\n\n[[runners]]\nname = \"a\"\nscript = \"y\"\nrestrat = \"always\"\n",
+            ),
+            [field("script", None), field("restrat", Some("a"))]
+        );
+
+        let path = dir.join("config.yaml");
+        assert_eq!(
+            unknown_fields(
+                &path,
+                "runners: []\nlevel_colors:\n  levels:\n    - { token: E, color: '#f00', bold: true }\n",
+            ),
+            [field("bold", None)]
+        );
+        assert_eq!(
+            unknown_fields(&path, "runners: []\nthem: Dark\n"),
+            [field("them", None)]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn level_colors_are_off_by_default() {
+        let config = parse(r#"{ "runners": [] }"#, Format::Json).unwrap();
+        assert!(!config.level_colors.enabled);
+        assert_eq!(config.level_colors.levels[0].token, "ERROR");
+
+        let toml = "runners = []\n[level_colors]\nenabled = true\nlevels = [{ token = \"FATAL\", color = \"#ff0000\" }]\n";
+        let config = parse(toml, Format::Toml).unwrap();
+        assert!(config.level_colors.enabled);
+        assert_eq!(config.level_colors.levels.len(), 1);
+        assert_eq!(
+            config.level_colors.levels[0].color,
+            iced::Color::from_rgb8(0xff, 0, 0)
+        );
+
+        let toml =
+            "runners = []\n[level_colors]\nlevels = [{ token = \"FATAL\", color = \"red\" }]\n";
+        assert!(parse(toml, Format::Toml).is_err());
+    }
+
+    #[test]
+    fn ready_when_must_be_a_valid_regex() {
+        let runner = |ready_when| {
+            format!(
+                r#"{{ "runners": [{{ "name": "a", "script": "a", "ready_when": "{ready_when}" }}] }}"#
+            )
+        };
+
+        let config = parse(&runner("Listening on :[0-9]+"), Format::Json).unwrap();
+        let ready_when = config.runners[0].ready_when.as_ref().unwrap();
+        assert!(ready_when.is_match("Listening on :8080"));
+
+        assert!(parse(&runner("Listening on ("), Format::Json).is_err());
+    }
+
+    #[test]
+    fn max_log_lines_defaults_to_the_configs() {
+        let toml = r#"
+            max_log_lines = 100
+
+            [[runners]]
+            name = "a"
+            script = "true"
+
+            [[runners]]
+            name = "b"
+            script = "true"
+            max_log_lines = 5
+        "#;
+        let mut config = parse(toml, Format::Toml).unwrap();
+        apply_defaults(&mut config);
+        assert_eq!(config.runners[0].max_log_lines, Some(100));
+        assert_eq!(config.runners[1].max_log_lines, Some(5));
+
+        let mut config = parse(r#"{ "runners": [{ "name": "a" }] }"#, Format::Json).unwrap();
+        apply_defaults(&mut config);
+        assert_eq!(config.runners[0].max_log_lines, None);
+    }
+
+    #[test]
+    fn environments_are_inherited_unless_cleared() {
+        let toml = r#"
+            [[runners]]
+            name = "a"
+            script = "true"
+
+            [[runners]]
+            name = "b"
+            script = "true"
+            env_clear = true
+            env = { PATH = "/usr/bin:/bin", RUST_LOG = "debug" }
+        "#;
+        let config = parse(toml, Format::Toml).unwrap();
+        assert!(!config.runners[0].env_clear);
+        assert!(config.runners[0].env.is_empty());
+        assert!(config.runners[1].env_clear);
+        assert_eq!(config.runners[1].env["RUST_LOG"], "debug");
+    }
+
+    #[test]
+    fn schedules_are_parsed() {
+        assert_eq!(
+            parse_duration("500ms"),
+            Some(std::time::Duration::from_millis(500))
+        );
+        assert_eq!(
+            parse_duration("1h30m"),
+            Some(std::time::Duration::from_secs(90 * 60))
+        );
+        assert_eq!(parse_duration("30"), None);
+        assert_eq!(parse_duration("30 fortnights"), None);
+
+        let toml = r#"
+            [[runners]]
+            name = "sync"
+            script = "true"
+            schedule = { every = "30s" }
+
+            [[runners]]
+            name = "report"
+            script = "true"
+            schedule = { cron = "0 0 9 * * *" }
+        "#;
+        let config = parse(toml, Format::Toml).unwrap();
+        let start = std::time::SystemTime::UNIX_EPOCH;
+        let next = |i: usize| {
+            let schedule = config.runners[i].schedule.as_ref().unwrap();
+            schedule.next_after(start).unwrap()
+        };
+        assert_eq!(next(0), start + std::time::Duration::from_secs(30));
+        assert!(next(1) > start);
+
+        let yaml = "runners: [{ name: sync, script: 'true', schedule: { every: 0s } }]";
+        assert!(parse(yaml, Format::Yaml).is_err());
+        let yaml = "runners: [{ name: sync, script: 'true', schedule: { cron: '* *' } }]";
+        assert!(parse(yaml, Format::Yaml).is_err());
+    }
+
+    #[test]
+    fn glyphs_and_stderr_color_are_optional() {
+        let config = parse(r#"{ "runners": [] }"#, Format::Json).unwrap();
+        assert_eq!(config.glyphs.stdout, crate::app::GLYPH_STDOUT);
+        assert_eq!(config.stderr_color, None);
+
+        let config = parse(
+            r##"{ "runners": [], "glyphs": { "stderr": "stderr" }, "stderr_color": "#ff0000" }"##,
+            Format::Json,
+        )
+        .unwrap();
+        assert_eq!(config.glyphs.stdout, crate::app::GLYPH_STDOUT);
+        assert_eq!(config.glyphs.stderr, "stderr");
+        assert_eq!(
+            config.stderr_color,
+            Some(iced::Color::from_rgb(1.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn themes_are_found_by_name() {
+        let theme = |theme: &str| {
+            parse(
+                &format!(r#"{{ "runners": [], "theme": "{theme}" }}"#),
+                Format::Json,
+            )
+            .map(|config| config.theme)
+        };
+        assert_eq!(theme("Dark"), Ok(Some(iced::Theme::Dark)));
+        assert_eq!(theme("tokyo_night"), Ok(Some(iced::Theme::TokyoNight)));
+        assert_eq!(
+            theme("Catppuccin Mocha"),
+            Ok(Some(iced::Theme::CatppuccinMocha))
+        );
+        assert!(theme("Beige").is_err());
+    }
+
+    #[test]
+    fn includes_merge_and_detect_cycles() {
+        let dir = std::env::temp_dir().join(format!("battlestation-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+
+        std::fs::write(
+            dir.join("main.json"),
+            r#"{ "include": ["sub/a.toml"], "runners": [{ "name": "a", "script": "main a" }] }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("sub/a.toml"),
+            "[[runners]]\nname = \"a\"\nscript = \"sub a\"\n\
+             [[runners]]\nname = \"b\"\nscript = \"sub b\"\n",
+        )
+        .unwrap();
+
+        let config = load_config(&dir.join("main.json")).unwrap();
+        let runners = config
+            .runners
+            .iter()
+            .map(|r| (r.name.as_str(), r.script.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(runners, [("a", "main a"), ("b", "sub b")]);
+
+        std::fs::write(
+            dir.join("sub/a.toml"),
+            "include = [\"../main.json\"]\nrunners = []\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_config(&dir.join("main.json")),
+            Err(Error::IncludeCycle(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn script_files_are_read_relative_to_their_config() {
+        let dir = std::env::temp_dir().join(format!(
+            "battlestation-script-file-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("sub/scripts")).unwrap();
+
+        std::fs::write(
+            dir.join("main.json"),
+            r#"{ "include": ["sub/a.toml"], "runners": [] }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("sub/a.toml"),
+            "[[runners]]\nname = \"a\"\nscript_file = \"scripts/a.sh\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("sub/scripts/a.sh"), "echo a\necho b\n").unwrap();
+
+        let config = load_config(&dir.join("main.json")).unwrap();
+        assert_eq!(config.runners[0].script, "echo a\necho b\n");
+
+        std::fs::write(
+            dir.join("sub/a.toml"),
+            "[[runners]]\nname = \"a\"\nscript = \"echo\"\nscript_file = \"scripts/a.sh\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_config(&dir.join("main.json")),
+            Err(Error::ExclusiveFields {
+                fields: ["script", "script_file"],
+                ..
+            })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let config = parse(
+            r#"{ "runners": [
+                { "name": "db", "script": "db" },
+                { "name": "api", "script": "api", "depends_on": ["db", "worker"] },
+                { "name": "worker", "script": "worker", "depends_on": ["api"] }
+            ] }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        match check_dependencies(&config) {
+            Err(Error::DependencyCycle(names)) => assert_eq!(names, ["api", "worker", "api"]),
+            _ => panic!("expected a dependency cycle"),
+        }
+
+        let config = parse(
+            r#"{ "runners": [{ "name": "api", "script": "api", "depends_on": ["db"] }] }"#,
+            Format::Json,
+        )
+        .unwrap();
+        assert!(matches!(
+            check_dependencies(&config),
+            Err(Error::UnknownDependency { .. })
+        ));
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let config = parse(
+            r#"{ "runners": [
+                { "name": "a", "script": "echo a" },
+                { "name": "b", "script": "echo b" },
+                { "name": "a", "script": "echo a again" }
+            ] }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        match check_names(&config) {
+            Err(Error::DuplicateName {
+                name,
+                first,
+                duplicate,
+            }) => assert_eq!((name.as_str(), first, duplicate), ("a", 0, 2)),
+            _ => panic!("expected a duplicate name"),
+        }
+    }
+
+    #[test]
+    fn script_and_command_are_exclusive() {
+        let config = parse(
+            r#"{ "runners": [
+                { "name": "a", "command": ["echo", "a"] },
+                { "name": "b", "script": "echo b", "command": ["echo", "b"] }
+            ] }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        match check_scripts(&config) {
+            Err(Error::ExclusiveFields { runner, fields }) => {
+                assert_eq!((runner.as_str(), fields), ("b", ["script", "command"]))
+            }
+            _ => panic!("expected exclusive fields"),
+        }
+    }
+
+    #[test]
+    fn scripts_or_commands_are_required() {
+        let check = |runner: &str| {
+            let config = parse(&format!(r#"{{ "runners": [{runner}] }}"#), Format::Json).unwrap();
+            check_scripts(&config)
+        };
+        assert!(matches!(
+            check(r#"{ "name": "a" }"#),
+            Err(Error::MissingScript { runner }) if runner == "a"
+        ));
+        assert!(matches!(
+            check(r#"{ "name": "b", "command": [] }"#),
+            Err(Error::EmptyCommand { runner }) if runner == "b"
+        ));
+        assert!(matches!(
+            check(r#"{ "name": "c", "command": [""] }"#),
+            Err(Error::EmptyCommand { .. })
+        ));
+        assert!(check(r#"{ "name": "d", "command": ["true"] }"#).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "api")]
+    fn single_runners_are_parsed() {
+        let parse_runner = |contents| parse_runner(contents, Path::new("."));
+        let runner = parse_runner(r#"{ "name": "a", "script": "echo a", "tags": ["x"] }"#).unwrap();
+        assert_eq!(
+            (runner.name.as_str(), runner.tags.as_slice()),
+            ("a", &["x".to_string()][..])
+        );
+        assert!(parse_runner(r#"{ "script": "echo a" }"#).is_err());
+        let both = r#"{ "name": "b", "script": "echo b", "command": ["echo", "b"] }"#;
+        assert!(
+            parse_runner(both)
+                .err()
+                .is_some_and(|err| err.contains("can't set both script and command"))
+        );
+
+        // Script files are found next to the config, not where we run
+        let dir = std::env::temp_dir().join(format!(
+            "battlestation-parse-runner-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("scripts")).unwrap();
+        std::fs::write(dir.join("scripts/c.sh"), "echo c\n").unwrap();
+        let runner =
+            super::parse_runner(r#"{ "name": "c", "script_file": "scripts/c.sh" }"#, &dir).unwrap();
+        assert_eq!(runner.script, "echo c\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_finds_problems() {
+        let config = parse(
+            r#"{ "runners": [
+                { "name": "a", "script": "echo a" },
+                { "name": "b", "script": " " },
+                { "name": "c", "script": "echo c", "read_buffer_size": 0 },
+                { "name": "d", "script": "echo d", "max_line_len": 0 },
+                { "name": "e", "command": [] },
+                { "name": "f", "script": "echo f", "encoding": "klingon" }
+            ], "text_size": 0 }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        let problems = validate(&config);
+        assert_eq!(problems.len(), 6);
+        assert!(problems[0].contains("script is empty"));
+        assert!(problems[1].contains("read_buffer_size"));
+        assert!(problems[2].contains("max_line_len"));
+        assert!(problems[3].contains("command is empty"));
+        assert!(problems[4].contains("unknown encoding"));
+        assert!(problems[5].contains("text_size"));
+    }
+}
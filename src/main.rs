@@ -1,8 +1,19 @@
+mod ansi;
+#[cfg(feature = "api")]
+mod api;
 mod app;
+mod config;
 mod icon;
+mod notify;
+mod run;
 mod runner;
+mod serve;
+mod session;
+mod usage;
+mod watch;
 
 use app::App;
+use config::RunnerConfig;
 use runner::Runner;
 
 use clap::{Parser, Subcommand};
@@ -12,195 +23,400 @@ use clap::{Parser, Subcommand};
 struct Args {
     #[command(subcommand)]
     command: Command,
+    /// Level of battlestation's own logging, written to stderr.
+    /// RUST_LOG is used if this isn't given, defaulting to info
+    #[arg(long, global = true)]
+    log_level: Option<tracing::Level>,
+    /// Don't log anything of our own
+    #[arg(short, long, global = true, conflicts_with = "log_level")]
+    quiet: bool,
 }
 
 #[derive(Subcommand)]
 enum Command {
     /// Run the battlestation UI (default)
     UI {
+        /// Path to config. Defaults to
+        /// $XDG_CONFIG_HOME/battlestation/config.{json,toml,yaml}
+        /// or ~/.config/battlestation/config.{json,toml,yaml}.
+        /// Use "-" to read a JSON config from stdin
+        #[arg(short, long)]
+        config: Option<String>,
+        /// Write every runner's log history to this file on exit,
+        /// as JSON lines for .jsonl or .ndjson files, or text otherwise
+        #[arg(long)]
+        log_export_on_exit: Option<std::path::PathBuf>,
+    },
+    /// Run every runner without the UI, printing their output, until they
+    /// all finish or we're interrupted
+    Serve {
+        /// Path to config, found the same way as for the UI
+        #[arg(short, long)]
+        config: Option<String>,
+    },
+    /// Check a config for problems without running anything
+    Validate {
         #[arg(short, long)]
         config: String,
     },
+    /// List the runners defined in a config, one per line
+    List {
+        #[arg(short, long)]
+        config: String,
+        /// Also print each runner's script, tab separated
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Print a TOML config with a runner for each executable *.sh file in
+    /// a directory, to redirect to a file and edit
+    Import {
+        dir: std::path::PathBuf,
+        /// Directory the config will be saved in, which script paths are
+        /// written relative to
+        #[arg(long, default_value = ".")]
+        config_dir: std::path::PathBuf,
+    },
     /// Run a command, ensure children are cleaned up in SIGTERM
     Run {
         /// Run command in a subshell
-        #[arg(short)]
-        command_string: String,
+        #[arg(short, required_unless_present = "argv", conflicts_with = "argv")]
+        command_string: Option<String>,
+        /// Program and arguments to run directly, without a shell
+        #[arg(last = true)]
+        argv: Vec<String>,
+        /// How long to wait after forwarding a deadly signal before SIGKILL
+        #[arg(long, default_value_t = runner::DEFAULT_KILL_TIMEOUT_MS)]
+        kill_timeout_ms: u64,
+        /// Start a new session, detaching the command from our controlling
+        /// terminal. Unix only.
+        #[arg(long)]
+        new_session: bool,
     },
 }
 
-#[derive(serde::Deserialize)]
-struct Config {
-    runners: Vec<RunnerConfig>,
+impl From<RunnerConfig> for runner::Runner {
+    fn from(rc: RunnerConfig) -> runner::Runner {
+        let mut runner = Runner::new(rc.name, rc.script);
+        runner.command = rc.command;
+        runner.description = rc.description;
+        runner.tags = rc.tags;
+        runner.auto_start = rc.auto_start;
+        runner.depends_on = rc.depends_on;
+        runner.start_delay = rc.start_delay_ms.map(std::time::Duration::from_millis);
+        runner.restart = rc.restart;
+        if let Some(backoff_base_ms) = rc.backoff_base_ms {
+            runner.backoff_base = std::time::Duration::from_millis(backoff_base_ms);
+        }
+        if let Some(backoff_cap_ms) = rc.backoff_cap_ms {
+            runner.backoff_cap = std::time::Duration::from_millis(backoff_cap_ms);
+        }
+        runner.max_restarts = rc.max_restarts;
+        if let Some(kill_timeout_ms) = rc.kill_timeout_ms {
+            runner.kill_timeout = std::time::Duration::from_millis(kill_timeout_ms);
+        }
+        runner.new_session = rc.new_session;
+        runner.env = rc.env;
+        runner.env_clear = rc.env_clear;
+        runner.health_check = rc.health_check;
+        runner.ready_when = rc.ready_when;
+        if let Some(read_buffer_size) = rc.read_buffer_size {
+            // An empty buffer would read as the end of output
+            runner.read_buffer_size = read_buffer_size.max(1);
+        }
+        if let Some(label) = &rc.encoding {
+            match encoding_rs::Encoding::for_label(label.as_bytes()) {
+                Some(encoding) => runner.encoding = encoding,
+                None => {
+                    tracing::warn!(runner = %runner.name, "unknown encoding {label:?}, using UTF-8")
+                }
+            }
+        }
+        if let Some(max_line_len) = rc.max_line_len {
+            runner.max_line_len = max_line_len.max(1);
+        }
+        runner.max_log_lines = rc.max_log_lines;
+        runner.strip_ansi = rc.strip_ansi;
+        runner.collapse_repeats = rc.collapse_repeats;
+        runner.notify_on_complete = rc.notify_on_complete;
+        runner.confirm_kill = rc.confirm_kill;
+        runner.color = rc.color;
+        runner.watch = rc.watch;
+        runner.watch_always = rc.watch_always;
+        if let Some(watch_debounce_ms) = rc.watch_debounce_ms {
+            runner.watch_debounce = std::time::Duration::from_millis(watch_debounce_ms);
+        }
+        runner.timeout = rc.timeout_ms.map(std::time::Duration::from_millis);
+        runner.schedule = rc.schedule;
+        runner.stall_timeout = rc.stall_timeout_ms.map(std::time::Duration::from_millis);
+        runner.notify_on_stall = rc.notify_on_stall;
+        runner.set_activity_lengths(
+            rc.activity_on_ms.map(std::time::Duration::from_millis),
+            rc.activity_off_ms.map(std::time::Duration::from_millis),
+        );
+        runner
+    }
 }
 
-#[derive(serde::Deserialize)]
-struct RunnerConfig {
-    name: String,
-    script: String,
+/// Log to stderr, filtered by `--log-level`, `--quiet` or RUST_LOG
+fn init_tracing(log_level: Option<tracing::Level>, quiet: bool) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = if quiet {
+        EnvFilter::new("off")
+    } else if let Some(level) = log_level {
+        EnvFilter::new(format!("battlestation={level}"))
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("battlestation=info"))
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+        .init();
 }
 
-impl From<RunnerConfig> for runner::Runner {
-    fn from(rc: RunnerConfig) -> runner::Runner {
-        Runner::new(rc.name, rc.script)
+/// Load a config, reporting errors the same way for every subcommand
+fn load_config(path: &std::path::Path) -> Option<config::Config> {
+    use clap::CommandFactory;
+
+    match config::load_config(path) {
+        Ok(config) => Some(config),
+        Err(
+            e @ (config::Error::Io(..)
+            | config::Error::Empty(..)
+            | config::Error::UnknownFields { .. }),
+        ) => Args::command()
+            .error(clap::error::ErrorKind::ValueValidation, e.to_string())
+            .exit(),
+        Err(e) => {
+            println!("{e}");
+            None
+        }
+    }
+}
+
+/// The config given, or else the default one, exiting if there's neither
+fn config_path(config: Option<String>) -> std::path::PathBuf {
+    use clap::CommandFactory;
+
+    match config {
+        Some(config) => std::path::PathBuf::from(config),
+        None => match config::find_default() {
+            Some(path) => path,
+            None => {
+                let dirs = config::default_dirs()
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Args::command()
+                    .error(
+                        clap::error::ErrorKind::MissingRequiredArgument,
+                        format!("No --config given and no config found in: {dirs}"),
+                    )
+                    .exit()
+            }
+        },
     }
 }
 
 fn main() -> std::process::ExitCode {
     let args = Args::parse();
+    init_tracing(args.log_level, args.quiet);
 
     match args.command {
-        Command::UI { config } => {
-            let config = match std::fs::read_to_string(&config) {
-                Ok(fp) => fp,
-                Err(e) => {
-                    use clap::CommandFactory;
-                    Args::command()
-                        .error(
-                            clap::error::ErrorKind::ValueValidation,
-                            format!("Error opening config file {config}: {e}"),
-                        )
-                        .exit()
-                }
+        Command::UI {
+            config,
+            log_export_on_exit,
+        } => {
+            let config_path = config_path(config);
+            tracing::info!("loading config from {}", config::display_path(&config_path));
+
+            let Some(config) = load_config(&config_path) else {
+                return std::process::ExitCode::FAILURE;
             };
 
-            let config = match serde_json::from_str::<Config>(&config) {
-                Ok(config) => config,
-                Err(e) => {
-                    println!("Error parsing json: {e}");
-                    return std::process::ExitCode::FAILURE;
-                }
+            let session = config
+                .save_session
+                .then(|| session::path(&config_path))
+                .flatten()
+                .map(|path| (session::load(&path), path));
+
+            let text_size = match config.text_size {
+                Some(text_size) if text_size.is_finite() && text_size > 0.0 => text_size,
+                _ => iced::Settings::default().default_text_size.0,
             };
 
-            let res = iced::application("Battlestation", App::update, App::view)
+            let mut application = iced::application(App::title, App::update, App::view)
+                .settings(iced::Settings {
+                    default_text_size: text_size.into(),
+                    ..Default::default()
+                })
                 .font(icon::ICON_FONT_BYTES)
-                .run_with(|| {
-                    let app = App::new(config.runners.into_iter().map(Into::into).collect());
+                .subscription(App::subscription)
+                .theme(App::theme)
+                // Closing is handled by App, to export logs first
+                .exit_on_close_request(false);
+            if let Some(font_file) = &config.font_file {
+                let font_path = config::base_dir(&config_path).join(font_file);
+                match std::fs::read(&font_path) {
+                    Ok(bytes) => application = application.font(bytes),
+                    Err(err) => {
+                        tracing::warn!("unable to load font {}: {err}", font_path.display())
+                    }
+                }
+            }
+            if let Some(window) = session
+                .as_ref()
+                .and_then(|(session, _)| session.window?.restorable())
+            {
+                application = application.window_size((window.width, window.height));
+                if let Some((x, y)) = window.position {
+                    let position = iced::Point::new(x, y);
+                    application = application.position(iced::window::Position::Specific(position));
+                }
+            }
+
+            let res = application.run_with(move || {
+                let mut app = App::new(config.runners.into_iter().map(Into::into).collect());
+                app.log_export_on_exit = log_export_on_exit;
+                app.notify_on_failure = config.notify_on_failure;
+                if let Some(theme) = config.theme {
+                    app.theme = theme;
+                }
+                if let Some(font) = config.font {
+                    // iced looks fonts up by name for as long as it runs
+                    app.mono_font = iced::Font::with_name(font.leak());
+                }
+                app.set_text_size(text_size);
+                app.stdout_glyph = config.glyphs.stdout;
+                app.stderr_glyph = config.glyphs.stderr;
+                app.stderr_color = config.stderr_color;
+                if let Some(tab_width) = config.tab_width {
+                    app.tab_width = tab_width;
+                }
+                #[cfg(feature = "api")]
+                {
+                    app.api_addr = config.api_addr;
+                    app.config_dir = config::base_dir(&config_path).to_path_buf();
+                }
+                #[cfg(not(feature = "api"))]
+                if config.api_addr.is_some() {
+                    tracing::warn!("ignoring api_addr, as the api feature isn't enabled");
+                }
+                if let Some(overscan) = config.log_overscan {
+                    app.set_log_overscan(overscan);
+                }
+                if config.level_colors.enabled {
+                    app.level_colors = config
+                        .level_colors
+                        .levels
+                        .into_iter()
+                        .map(|level| (level.token, level.color))
+                        .collect();
+                }
+                let mut task = iced::Task::batch([app.auto_start(), app.start_schedules()]);
+                if let Some((session, session_path)) = session {
+                    let restore = app.restore_session(session);
+                    task = iced::Task::batch([restore, task]);
+                    app.session_path = Some(session_path);
+                }
 
-                    (app, iced::Task::none())
-                });
+                (app, task)
+            });
 
             if let Err(e) = res {
-                println!("Exiting with error: {e:?}");
+                tracing::error!("exiting with error: {e:?}");
                 std::process::ExitCode::FAILURE
             } else {
                 std::process::ExitCode::SUCCESS
             }
         }
 
-        Command::Run { command_string } => {
-            // println! will panic when stdout is closed.
-            // Use write! to log file instead
-            use std::io::Write;
-            //let pid = unsafe { libc::getpid() };
-            //let mut w = std::fs::File::create(format!("log-{pid}.txt")).unwrap();
-            let mut w = std::fs::File::create("/dev/null").unwrap();
-
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-
-            rt.block_on(async {
-                let mut command = tokio::process::Command::new("/bin/bash");
-                command.arg("-c");
-                command.arg(command_string);
-
-                // Get sudo to make gui prompt for password
-                command.env(
-                    "SUDO_ASKPASS",
-                    "/Users/poconbhui/prog/battlestation/_askpass.sh",
-                );
-
-                // Make new session, disconnecting tty
-                let _ = unsafe { libc::setsid() };
-
-                // Set PGID of command to child_pid, so we can use killpg
-                command.process_group(0);
-
-                let mut child = command.spawn().unwrap();
-                let child_pid = child.id().unwrap() as i32;
-                let _ = writeln!(&mut w, "child_pid: {child_pid}");
-
-                // Check if parent died by checking if this process has been
-                // reparented
-                let parent_died = async {
-                    let prev_ppid = unsafe { libc::getppid() };
-                    loop {
-                        let current_ppid = unsafe { libc::getppid() };
-                        if current_ppid != prev_ppid {
-                            let _ = writeln!(
-                                &mut w,
-                                "parent died. prev {prev_ppid} now {current_ppid}"
-                            );
-                            return;
-                        }
-
-                        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                    }
-                };
+        Command::Serve { config } => {
+            let config_path = config_path(config);
+            tracing::info!("loading config from {}", config::display_path(&config_path));
 
-                let signal_listener = async |raw_signal| {
-                    let mut listener = tokio::signal::unix::signal(
-                        tokio::signal::unix::SignalKind::from_raw(raw_signal),
-                    )
-                    .unwrap();
-                    listener.recv().await
-                };
-
-                tokio::select! {
-                    _ = child.wait() => {},
-                    // Kill our child when our parent dies
-                    _ = parent_died => {
-                        let _ = writeln!(&mut w, "Parent dies, cleaning up");
-                        unsafe { libc::kill(child_pid, libc::SIGTERM) };
-                    },
-                    // Forward signals
-                    _ = signal_listener(libc::SIGINT) => {
-                        let _ = writeln!(&mut w, "GOT SIGINT");
-                        unsafe { libc::kill(child_pid, libc::SIGINT) };
-                    },
-                    _ = signal_listener(libc::SIGTERM) => {
-                        let _ = writeln!(&mut w, "GOT SIGTERM");
-                        unsafe { libc::kill(child_pid, libc::SIGTERM) };
-                    },
-                    _ = signal_listener(libc::SIGPIPE) => {
-                        let _ = writeln!(&mut w, "GOT SIGPIPE");
-                        unsafe { libc::kill(child_pid, libc::SIGPIPE) };
-                    }
-                };
-
-                // Child has finished, or been sent a deadly signal.
-                // Wait a bit, and kill it if it doesn't finish
-                tokio::select! {
-                    res = child.wait() => {
-                        let _ = writeln!(&mut w, "child closed cleanly: {:?}", res);
-                    },
-                    _ = tokio::time::sleep(tokio::time::Duration::from_millis(5000)) => {
-                        let _ = writeln!(&mut w, "chile timed out");
-                        unsafe { libc::kill(child_pid, libc::SIGKILL) };
-                    }
-                }
+            let Some(config) = load_config(&config_path) else {
+                return std::process::ExitCode::FAILURE;
+            };
 
-                // Child has finished, or been send a very deadly signal.
-                let child_res = child.wait().await;
+            serve::serve(config.runners.into_iter().map(Into::into).collect())
+        }
 
-                // Child is dead, cleanup any stragglers
-                let _ = writeln!(&mut w, "cleanup stragglers");
-                unsafe { libc::killpg(child_pid, libc::SIGTERM) };
+        Command::Validate { config } => {
+            let config_path = std::path::PathBuf::from(config);
+            let Some(config) = load_config(&config_path) else {
+                return std::process::ExitCode::FAILURE;
+            };
 
-                if let Ok(child_res) = child_res {
-                    if child_res.success() {
-                        std::process::ExitCode::SUCCESS
-                    } else {
-                        let _ = writeln!(&mut w, "Child exited with error: {child_res:?}");
-                        std::process::ExitCode::FAILURE
-                    }
+            let problems = config::validate(&config);
+            for problem in &problems {
+                println!("{problem}");
+            }
+
+            let config_path = config::display_path(&config_path);
+            let n_runners = config.runners.len();
+            if problems.is_empty() {
+                println!("{config_path}: {n_runners} runners, ok");
+                std::process::ExitCode::SUCCESS
+            } else {
+                let n_problems = problems.len();
+                println!("{config_path}: {n_runners} runners, {n_problems} problems");
+                std::process::ExitCode::FAILURE
+            }
+        }
+
+        Command::List { config, verbose } => {
+            let Some(config) = load_config(std::path::Path::new(&config)) else {
+                return std::process::ExitCode::FAILURE;
+            };
+
+            for runner in &config.runners {
+                if verbose {
+                    // Keep one runner per line for multi-line scripts
+                    let script = match &runner.command {
+                        Some(command) => command.join(" "),
+                        None => runner.script.replace('\n', "\\n"),
+                    };
+                    println!("{}\t{script}", runner.name);
                 } else {
-                    let _ = writeln!(&mut w, "Error getting child result: {child_res:?}");
-                    std::process::ExitCode::FAILURE
+                    println!("{}", runner.name);
                 }
-            })
+            }
+
+            std::process::ExitCode::SUCCESS
+        }
+
+        Command::Import { dir, config_dir } => match config::import_scripts(&dir, &config_dir) {
+            Ok(config) => {
+                print!("{config}");
+                std::process::ExitCode::SUCCESS
+            }
+            Err(err) => {
+                use clap::CommandFactory;
+
+                Args::command()
+                    .error(
+                        clap::error::ErrorKind::ValueValidation,
+                        format!("Error reading {}: {err}", dir.display()),
+                    )
+                    .exit()
+            }
+        },
+
+        Command::Run {
+            command_string,
+            argv,
+            kill_timeout_ms,
+            new_session,
+        } => {
+            let script = match command_string {
+                Some(command_string) => run::Script::Shell(command_string),
+                None => run::Script::Argv(argv),
+            };
+            run::run(script, kill_timeout_ms, new_session)
         }
     }
 }
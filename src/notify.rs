@@ -0,0 +1,15 @@
+//! Desktop notifications about runners.
+
+/// Show a desktop notification without waiting for it to be delivered
+pub fn send(summary: String, body: String) {
+    std::thread::spawn(move || {
+        let result = notify_rust::Notification::new()
+            .appname("Battlestation")
+            .summary(&summary)
+            .body(&body)
+            .show();
+        if let Err(err) = result {
+            tracing::warn!("unable to notify: {err}");
+        }
+    });
+}
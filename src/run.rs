@@ -0,0 +1,20 @@
+//! The `run` wrapper, which runs a script and makes sure its children are
+//! cleaned up when it's killed, or when we are.
+
+/// What the wrapper runs
+pub enum Script {
+    /// A command string for the platform's shell
+    Shell(String),
+    /// A program and its arguments, run directly
+    Argv(Vec<String>),
+}
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::run;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use windows::run;
@@ -0,0 +1,310 @@
+//! The `run` wrapper on Unix, which keeps the script in its own process
+//! group and forwards signals to it.
+
+use tokio_stream::StreamExt;
+
+use super::Script;
+
+/// Signals that ask the child to exit. After the first of these, the child
+/// is given the kill timeout to finish before it's sent SIGKILL.
+const DEADLY_SIGNALS: [i32; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGPIPE];
+
+/// Signals that are passed on without ending the child's life. Programs
+/// that exit on SIGQUIT are left to do so, as some just dump state.
+const FORWARDED_SIGNALS: [i32; 6] = [
+    libc::SIGTSTP,
+    libc::SIGCONT,
+    libc::SIGHUP,
+    libc::SIGQUIT,
+    libc::SIGUSR1,
+    libc::SIGUSR2,
+];
+
+/// Run `script`, in bash if it's a command string, forwarding signals
+/// until it exits. With `new_session`, it's also detached from our
+/// controlling terminal.
+pub fn run(script: Script, kill_timeout_ms: u64, new_session: bool) -> std::process::ExitCode {
+    // Our stderr is the runner's, so anything logged here shows up
+    // in its logs. Logging ignores write errors, unlike println!,
+    // which panics if the pipe is closed.
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let parent_pid = unsafe { libc::getppid() };
+
+    rt.block_on(async {
+        let mut command = match script {
+            Script::Shell(command_string) => {
+                let mut command = tokio::process::Command::new("/bin/bash");
+                command.arg("-c");
+                command.arg(command_string);
+                command
+            }
+            Script::Argv(argv) => {
+                let mut command = tokio::process::Command::new(&argv[0]);
+                command.args(&argv[1..]);
+                command
+            }
+        };
+
+        // Get sudo to make gui prompt for password
+        command.env(
+            "SUDO_ASKPASS",
+            "/Users/poconbhui/prog/battlestation/_askpass.sh",
+        );
+
+        if new_session {
+            // Make new session, disconnecting tty
+            let _ = unsafe { libc::setsid() };
+        }
+
+        // Set PGID of command to child_pid, so we can use killpg. This
+        // needs no new session, the group is just in ours.
+        command.process_group(0);
+
+        // Listen before spawning, so no signal is missed
+        let mut signals = tokio_stream::StreamMap::new();
+        for raw_signal in DEADLY_SIGNALS.into_iter().chain(FORWARDED_SIGNALS) {
+            let listener =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::from_raw(raw_signal))
+                    .unwrap();
+            signals.insert(
+                raw_signal,
+                tokio_stream::wrappers::SignalStream::new(listener),
+            );
+        }
+        let signals = signals.map(|(raw_signal, ())| raw_signal);
+
+        // Now SIGTERM is handled, have the kernel send it when our parent
+        // dies. This is per thread, but runners are spawned from the
+        // runtime's worker threads, which live as long as the app.
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM)
+        };
+
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                // Our stderr is the runner's log, so this is shown whatever
+                // the log level
+                use std::io::Write;
+                let program = command.as_std().get_program().to_string_lossy();
+                let _ = writeln!(std::io::stderr(), "Unable to start {program}: {err}");
+                return std::process::ExitCode::FAILURE;
+            }
+        };
+        let child_pid = child.id().map(|pid| pid as i32);
+        tracing::debug!("child_pid: {child_pid:?}");
+
+        let child_res = supervise(
+            child,
+            signals,
+            parent_died(parent_pid),
+            std::time::Duration::from_millis(kill_timeout_ms),
+        )
+        .await;
+
+        // Child is dead, cleanup any stragglers
+        tracing::debug!("cleanup stragglers");
+        if let Some(child_pid) = child_pid {
+            unsafe { libc::killpg(child_pid, libc::SIGTERM) };
+        }
+
+        match child_res {
+            Ok(child_res) if child_res.success() => std::process::ExitCode::SUCCESS,
+            Ok(child_res) => {
+                tracing::debug!("Child exited with error: {child_res:?}");
+                if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&child_res) {
+                    die_by(signal);
+                }
+                std::process::ExitCode::FAILURE
+            }
+            Err(err) => {
+                tracing::debug!("Error getting child result: {err:?}");
+                std::process::ExitCode::FAILURE
+            }
+        }
+    })
+}
+
+/// Die by `signal`, as the script did, so the runner can tell a crash from
+/// an exit with an error. Returns if the signal doesn't end us.
+fn die_by(signal: i32) {
+    // The script may have dumped core, but there's no point in us doing so
+    let no_core = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    unsafe {
+        libc::setrlimit(libc::RLIMIT_CORE, &no_core);
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Resolves if our parent died before PR_SET_PDEATHSIG was set. After
+/// that, we're sent SIGTERM instead.
+#[cfg(target_os = "linux")]
+async fn parent_died(parent_pid: i32) {
+    let current_ppid = unsafe { libc::getppid() };
+    if current_ppid != parent_pid {
+        tracing::debug!("parent died. prev {parent_pid} now {current_ppid}");
+        return;
+    }
+    std::future::pending().await
+}
+
+/// Resolves when our parent dies, found by checking if this process has
+/// been reparented
+#[cfg(not(target_os = "linux"))]
+async fn parent_died(parent_pid: i32) {
+    loop {
+        let current_ppid = unsafe { libc::getppid() };
+        if current_ppid != parent_pid {
+            tracing::debug!("parent died. prev {parent_pid} now {current_ppid}");
+            return;
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    }
+}
+
+/// Forward every signal from `signals` to `child` until it exits.
+/// Once it's been sent a deadly signal, or `parent_died` resolves, it has
+/// `kill_timeout` to finish before it's killed.
+async fn supervise(
+    mut child: tokio::process::Child,
+    mut signals: impl tokio_stream::Stream<Item = i32> + Unpin,
+    parent_died: impl std::future::Future<Output = ()>,
+    kill_timeout: std::time::Duration,
+) -> std::io::Result<std::process::ExitStatus> {
+    let Some(child_pid) = child.id() else {
+        // Already reaped
+        return child.wait().await;
+    };
+    let child_pid = child_pid as i32;
+
+    let mut parent_died = std::pin::pin!(parent_died);
+    let mut parent_alive = true;
+    let kill_deadline = tokio::time::sleep(std::time::Duration::MAX);
+    let mut kill_deadline = std::pin::pin!(kill_deadline);
+    let mut dying = false;
+
+    loop {
+        tokio::select! {
+            res = child.wait() => {
+                tracing::debug!("child closed: {res:?}");
+                return res;
+            },
+            Some(raw_signal) = signals.next() => {
+                tracing::debug!("got signal {raw_signal}");
+                forward(child_pid, raw_signal);
+                if DEADLY_SIGNALS.contains(&raw_signal) && !dying {
+                    dying = true;
+                    kill_deadline.as_mut().reset(tokio::time::Instant::now() + kill_timeout);
+                }
+            },
+            // Kill our child when our parent dies
+            _ = &mut parent_died, if parent_alive => {
+                tracing::debug!("Parent died, cleaning up");
+                parent_alive = false;
+                unsafe { libc::kill(child_pid, libc::SIGTERM) };
+                if !dying {
+                    dying = true;
+                    kill_deadline.as_mut().reset(tokio::time::Instant::now() + kill_timeout);
+                }
+            },
+            _ = &mut kill_deadline, if dying => {
+                tracing::debug!("child timed out");
+                unsafe { libc::kill(child_pid, libc::SIGKILL) };
+                return child.wait().await;
+            },
+        }
+    }
+}
+
+/// Pass `raw_signal` on to the child.
+/// Pausing uses the whole process group, and as SIGSTOP can't be caught,
+/// pausing is requested with SIGTSTP.
+fn forward(child_pid: i32, raw_signal: i32) {
+    match raw_signal {
+        libc::SIGTSTP => unsafe { libc::killpg(child_pid, libc::SIGSTOP) },
+        libc::SIGCONT => unsafe { libc::killpg(child_pid, libc::SIGCONT) },
+        _ => unsafe { libc::kill(child_pid, raw_signal) },
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The exit code of `script` after it's sent `raw_signals` in turn.
+    /// The script should echo "ready" once its traps are set.
+    fn exit_code_after_signals(script: &str, raw_signals: &[i32]) -> Option<i32> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let mut command = tokio::process::Command::new("/bin/bash");
+            command.arg("-c").arg(script);
+            command.stdout(std::process::Stdio::piped());
+            command.process_group(0);
+            let mut child = command.spawn().unwrap();
+
+            // Wait for the traps to be set
+            let mut stdout = child.stdout.take().unwrap();
+            let mut ready = [0u8; 6];
+            tokio::io::AsyncReadExt::read_exact(&mut stdout, &mut ready)
+                .await
+                .unwrap();
+
+            let (signal_tx, signal_rx) = tokio::sync::mpsc::channel(1);
+            let signals = tokio_stream::wrappers::ReceiverStream::new(signal_rx);
+            let raw_signals = raw_signals.to_vec();
+            tokio::spawn(async move {
+                for raw_signal in raw_signals {
+                    signal_tx.send(raw_signal).await.unwrap();
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                // Keep the stream open
+                std::future::pending::<()>().await;
+            });
+
+            let res = supervise(
+                child,
+                signals,
+                std::future::pending(),
+                std::time::Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+            res.code()
+        })
+    }
+
+    #[test]
+    fn every_signal_is_forwarded() {
+        // Exits on the second SIGINT, like programs that force quit
+        let script = "n=0; trap 'n=$((n + 1)); [ $n -ge 2 ] && exit 3' INT; \
+                      echo ready; while true; do sleep 0.01; done";
+        assert_eq!(
+            exit_code_after_signals(script, &[libc::SIGINT, libc::SIGINT]),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn hangup_and_quit_are_forwarded() {
+        let script = "hup=0; trap 'hup=1' HUP; trap 'exit $((4 + hup))' QUIT; \
+                      echo ready; while true; do sleep 0.01; done";
+        assert_eq!(
+            exit_code_after_signals(script, &[libc::SIGHUP, libc::SIGQUIT]),
+            Some(5)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! The `run` wrapper on Windows, which keeps the script's process tree in a
+//! Job Object so it can be killed together.
+//!
+//! There are no signals to forward. Ctrl-C and Ctrl-Break reach the script
+//! through the console we share, so the script is just given the kill
+//! timeout to exit.
+
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject,
+};
+
+use super::Script;
+
+/// A Job Object that kills every process in it, once it's closed
+struct Job(HANDLE);
+
+impl Job {
+    fn new() -> std::io::Result<Job> {
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let job = Job(handle);
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let set = unsafe {
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of_val(&info) as u32,
+            )
+        };
+        if set == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(job)
+    }
+
+    /// Add `child` to the job. Processes it starts are added too.
+    fn assign(&self, child: &tokio::process::Child) -> std::io::Result<()> {
+        let Some(process) = child.raw_handle() else {
+            return Err(std::io::Error::other("child has already exited"));
+        };
+        if unsafe { AssignProcessToJobObject(self.0, process as HANDLE) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Kill every process in the job
+    fn terminate(&self) {
+        unsafe { TerminateJobObject(self.0, 1) };
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// Run `script`, in cmd if it's a command string, killing its process
+/// tree once it exits. If we're killed, closing the job kills the tree
+/// instead. There are no sessions to create, so `new_session` is ignored.
+pub fn run(script: Script, kill_timeout_ms: u64, _new_session: bool) -> std::process::ExitCode {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(async {
+        // Listen before spawning, so Ctrl-C doesn't end us before the script
+        let mut ctrl_c = tokio::signal::windows::ctrl_c().unwrap();
+        let mut ctrl_break = tokio::signal::windows::ctrl_break().unwrap();
+
+        let job = match Job::new() {
+            Ok(job) => job,
+            Err(err) => {
+                tracing::error!("unable to create job object: {err}");
+                return std::process::ExitCode::FAILURE;
+            }
+        };
+
+        let mut command = match script {
+            Script::Shell(command_string) => {
+                let mut command = tokio::process::Command::new("cmd");
+                command.arg("/C");
+                // cmd parses its own command line, so it's passed on untouched
+                command.raw_arg(command_string);
+                command
+            }
+            Script::Argv(argv) => {
+                let mut command = tokio::process::Command::new(&argv[0]);
+                command.args(&argv[1..]);
+                command
+            }
+        };
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                // Our stderr is the runner's log, so this is shown whatever
+                // the log level
+                use std::io::Write;
+                let program = command.as_std().get_program().to_string_lossy();
+                let _ = writeln!(std::io::stderr(), "Unable to start {program}: {err}");
+                return std::process::ExitCode::FAILURE;
+            }
+        };
+        tracing::debug!("child_pid: {:?}", child.id());
+
+        // Anything the script starts before this escapes the job, but that's
+        // a short window
+        if let Err(err) = job.assign(&child) {
+            tracing::warn!("unable to add child to job object: {err}");
+        }
+
+        let kill_timeout = std::time::Duration::from_millis(kill_timeout_ms);
+        let child_res = tokio::select! {
+            res = child.wait() => res,
+            _ = ctrl_c.recv() => {
+                tracing::debug!("GOT CTRL-C");
+                wait_or_kill(&mut child, kill_timeout).await
+            },
+            _ = ctrl_break.recv() => {
+                tracing::debug!("GOT CTRL-BREAK");
+                wait_or_kill(&mut child, kill_timeout).await
+            },
+        };
+
+        // Child is dead, cleanup any stragglers
+        tracing::debug!("cleanup stragglers");
+        job.terminate();
+
+        match child_res {
+            Ok(child_res) if child_res.success() => std::process::ExitCode::SUCCESS,
+            Ok(child_res) => {
+                tracing::debug!("Child exited with error: {child_res:?}");
+                std::process::ExitCode::FAILURE
+            }
+            Err(err) => {
+                tracing::debug!("Error getting child result: {err:?}");
+                std::process::ExitCode::FAILURE
+            }
+        }
+    })
+}
+
+/// Give `child` `kill_timeout` to exit before killing it
+async fn wait_or_kill(
+    child: &mut tokio::process::Child,
+    kill_timeout: std::time::Duration,
+) -> std::io::Result<std::process::ExitStatus> {
+    tokio::select! {
+        res = child.wait() => res,
+        _ = tokio::time::sleep(kill_timeout) => {
+            tracing::debug!("child timed out");
+            let _ = child.start_kill();
+            child.wait().await
+        }
+    }
+}
@@ -4,109 +4,745 @@ use tokio_stream::wrappers::ReceiverStream;
 
 pub struct Runner {
     pub name: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub auto_start: bool,
+    pub depends_on: Vec<String>,
+    /// Run requested, but held back until dependencies are ready
+    pub waiting: bool,
     script: String,
-    forever: bool,
+    /// Program and arguments run directly, instead of `script`
+    pub command: Option<Vec<String>>,
+    pub start_delay: Option<std::time::Duration>,
+    pub restart: RestartPolicy,
+    /// Shut down for good, so not restarted whatever `restart` says. Kept
+    /// apart from `restart`, which is the user's and saved with the session.
+    stopped_for_good: bool,
+    pub backoff_base: std::time::Duration,
+    pub backoff_cap: std::time::Duration,
+    consecutive_failures: u32,
+    pub max_restarts: Option<u32>,
+    restarts: u32,
+    /// Times the script has been spawned since it was last started by hand
+    runs: u32,
+    pub kill_timeout: std::time::Duration,
+    pub new_session: bool,
+    /// Set in the script's environment
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Don't inherit our environment, leaving only `env`
+    pub env_clear: bool,
+    /// Bytes read from stdout or stderr at a time
+    pub read_buffer_size: usize,
+    /// Encoding of the script's stdout and stderr
+    pub encoding: &'static encoding_rs::Encoding,
     status: Status,
+    succeeded: bool,
     stdout_activity: activity::Activity,
     stderr_activity: activity::Activity,
+    stdin_activity: activity::Activity,
+    stdin_input: String,
+    /// The script as it's being edited, until saved or cancelled. Saved
+    /// edits last until exit, and never change the config.
+    script_editor: Option<iced::widget::text_editor::Content>,
     pub show_logs: bool,
+    /// Oldest log lines are dropped past this many
+    pub max_log_lines: Option<usize>,
+    /// Output without a newline is logged in lines of at most this many
+    /// bytes, so the partial line can't grow forever
+    pub max_line_len: usize,
+    /// Show logs as plain text, ignoring ANSI colors
+    pub strip_ansi: bool,
+    /// Count lines repeating the one before, rather than logging them
+    pub collapse_repeats: bool,
+    /// Command run periodically while running, passing when it exits with 0
+    pub health_check: Option<String>,
+    /// Result of the last health check in this run, if any
+    healthy: Option<bool>,
+    /// CPU and memory use of the running script, sampled by the app
+    pub usage: Option<crate::usage::Usage>,
+    /// Dependents wait for a stdout line matching this, not just for the
+    /// script to be running
+    pub ready_when: Option<regex::Regex>,
+    /// Whether this run has printed a line matching `ready_when`
+    saw_ready_line: bool,
+    /// Trace output as it arrives
+    pub print_output: bool,
+    /// Show a desktop notification whenever the script exits
+    pub notify_on_complete: bool,
+    /// Ask before the stop button kills the script
+    pub confirm_kill: bool,
+    /// Restart when anything under these paths changes
+    pub watch: Vec<std::path::PathBuf>,
+    /// Start on changes while not running, too
+    pub watch_always: bool,
+    /// How long changes to `watch` must settle before restarting
+    pub watch_debounce: std::time::Duration,
+    /// Run again once the current run has been killed
+    rerun: bool,
+    /// Runs going on longer than this are killed
+    pub timeout: Option<std::time::Duration>,
+    /// Run whenever this comes due, unless already running
+    pub schedule: Option<Schedule>,
+    /// A run with no output for this long is marked as stalled
+    pub stall_timeout: Option<std::time::Duration>,
+    /// Show a desktop notification when a run stalls
+    pub notify_on_stall: bool,
+    /// Color of the name on the runner's log lines. If unset, one is
+    /// picked from a palette.
+    pub color: Option<iced::Color>,
 }
 
 enum Status {
     Off,
+    Delayed {
+        start_time: std::time::SystemTime,
+    },
     Running {
         start_time: std::time::SystemTime,
+        /// PID of the `run` wrapper, once it has spawned
+        pid: Option<i32>,
+        paused: bool,
         stdin_tx: mpsc::Sender<String>,
         kill_tx: Option<oneshot::Sender<()>>,
+        /// The wait for `timeout`, cancelled when dropped
+        timeout: Option<iced::task::Handle>,
+        /// Killed for running longer than `timeout`
+        timed_out: bool,
     },
     Completed {
         status: i32,
+        signal: Option<i32>,
         start_time: std::time::SystemTime,
-        _end_time: std::time::SystemTime,
+        end_time: std::time::SystemTime,
+        timed_out: bool,
+    },
+    /// Gave up restarting after `max_restarts`
+    Failed {
+        status: i32,
+    },
+}
+
+const DEFAULT_BACKOFF_BASE: std::time::Duration = std::time::Duration::from_millis(1000);
+const DEFAULT_BACKOFF_CAP: std::time::Duration = std::time::Duration::from_millis(60_000);
+
+pub const DEFAULT_KILL_TIMEOUT_MS: u64 = 5000;
+
+const DEFAULT_READ_BUFFER_SIZE: usize = 1024;
+
+const DEFAULT_MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Extra time given to the `run` wrapper to finish its own kill sequence
+pub const KILL_TIMEOUT_MARGIN: std::time::Duration = std::time::Duration::from_millis(1000);
+
+/// Runs lasting at least this long reset the restart backoff
+const BACKOFF_RESET_AFTER: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Time between health checks. A check still going after this long fails.
+const HEALTH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A signal that can be sent to a running script from the UI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signal {
+    name: &'static str,
+    raw: i32,
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Whether scripts can be paused, which needs SIGSTOP
+const CAN_PAUSE: bool = cfg!(unix);
+
+/// Signals the `run` wrapper forwards to the script
+#[cfg(windows)]
+const SIGNALS: &[Signal] = &[];
+/// Signals the `run` wrapper forwards to the script
+#[cfg(unix)]
+const SIGNALS: &[Signal] = &[
+    Signal {
+        name: "SIGHUP",
+        raw: libc::SIGHUP,
+    },
+    Signal {
+        name: "SIGINT",
+        raw: libc::SIGINT,
+    },
+    Signal {
+        name: "SIGQUIT",
+        raw: libc::SIGQUIT,
+    },
+    Signal {
+        name: "SIGTERM",
+        raw: libc::SIGTERM,
+    },
+    Signal {
+        name: "SIGUSR1",
+        raw: libc::SIGUSR1,
+    },
+    Signal {
+        name: "SIGUSR2",
+        raw: libc::SIGUSR2,
     },
+];
+
+/// How `signal` ended a script, in a word, and the signal's name,
+/// e.g. `("segfault", "SIGSEGV")`
+#[cfg(unix)]
+pub fn describe_signal(signal: i32) -> (&'static str, String) {
+    let (reason, name) = match signal {
+        libc::SIGKILL => ("killed", "SIGKILL"),
+        libc::SIGTERM => ("terminated", "SIGTERM"),
+        libc::SIGINT => ("interrupted", "SIGINT"),
+        libc::SIGHUP => ("hung up", "SIGHUP"),
+        libc::SIGQUIT => ("quit", "SIGQUIT"),
+        libc::SIGSEGV => ("segfault", "SIGSEGV"),
+        libc::SIGBUS => ("bus error", "SIGBUS"),
+        libc::SIGABRT => ("aborted", "SIGABRT"),
+        libc::SIGILL => ("illegal instruction", "SIGILL"),
+        libc::SIGFPE => ("arithmetic error", "SIGFPE"),
+        libc::SIGPIPE => ("broken pipe", "SIGPIPE"),
+        _ => return ("signalled", format!("signal {signal}")),
+    };
+    (reason, name.to_string())
+}
+
+/// Scripts aren't ended by signals without Unix
+#[cfg(not(unix))]
+pub fn describe_signal(signal: i32) -> (&'static str, String) {
+    ("signalled", format!("signal {signal}"))
+}
+
+/// Send `signal` to `pid`
+#[cfg(unix)]
+fn send_signal(pid: i32, signal: i32) {
+    unsafe { libc::kill(pid, signal) };
+}
+
+/// Stop or continue the script run by the wrapper at `pid`
+#[cfg(unix)]
+fn set_stopped(pid: i32, stopped: bool) {
+    send_signal(
+        pid,
+        if stopped {
+            libc::SIGTSTP
+        } else {
+            libc::SIGCONT
+        },
+    );
+}
+
+/// There are no signals on Windows, and none are offered
+#[cfg(windows)]
+fn send_signal(_pid: i32, _signal: i32) {}
+
+/// Scripts can't be paused on Windows, see `CAN_PAUSE`
+#[cfg(windows)]
+fn set_stopped(_pid: i32, _stopped: bool) {}
+
+/// Everything `Runner::exec` needs to know about how to run the script
+struct ExecConfig {
+    script: String,
+    command: Option<Vec<String>>,
+    kill_timeout: std::time::Duration,
+    new_session: bool,
+    env: std::collections::BTreeMap<String, String>,
+    env_clear: bool,
+    read_buffer_size: usize,
+    encoding: &'static encoding_rs::Encoding,
+}
+
+/// Whether a runner is run again once it completes
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    Always,
+    OnFailure,
+}
+
+impl RestartPolicy {
+    /// Whether to restart after completing with exit `status`
+    pub fn should_restart(self, status: i32) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => status != 0,
+        }
+    }
+
+    /// The policy after this one when cycling through them in the UI
+    fn next(self) -> RestartPolicy {
+        match self {
+            RestartPolicy::Never => RestartPolicy::Always,
+            RestartPolicy::Always => RestartPolicy::OnFailure,
+            RestartPolicy::OnFailure => RestartPolicy::Never,
+        }
+    }
+}
+
+/// When a scheduled runner is run
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Every so often, counting from when the last run was due
+    Every(std::time::Duration),
+    /// Whenever a cron expression matches the local time
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// When the next run is due after `time`, if ever
+    pub fn next_after(&self, time: std::time::SystemTime) -> Option<std::time::SystemTime> {
+        match self {
+            Schedule::Every(interval) => Some(time + *interval),
+            Schedule::Cron(schedule) => schedule
+                .after(&chrono::DateTime::<chrono::Local>::from(time))
+                .next()
+                .map(Into::into),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ActivityLight {
     Stdout,
     Stderr,
+    Stdin,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ScriptRun,
+    /// A run the user asked for, rather than a restart, schedule or watch.
+    /// It starts the run and restart counts afresh.
+    ScriptRunManual,
+    ScriptDelayElapsed {
+        start_time: std::time::SystemTime,
+    },
+    ScriptStarted {
+        start_time: std::time::SystemTime,
+        pid: i32,
+    },
+    ScriptPause {
+        start_time: std::time::SystemTime,
+    },
+    ScriptResume {
+        start_time: std::time::SystemTime,
+    },
+    ScriptSignal(i32),
     ScriptKill {
         start_time: std::time::SystemTime,
     },
+    /// Kill the running script, then run it again
+    ScriptRestart,
+    /// The run has gone on longer than `timeout`, and is to be killed
+    ScriptTimedOut {
+        start_time: std::time::SystemTime,
+    },
+    /// Handled by the app, which asks before sending `ScriptKill`
+    ConfirmKill {
+        start_time: std::time::SystemTime,
+    },
     ScriptComplete {
         status: i32,
+        /// The signal that ended the script, if one did
+        signal: Option<i32>,
         start_time: std::time::SystemTime,
         end_time: std::time::SystemTime,
     },
     ScriptClearStatus {
         start_time: std::time::SystemTime,
     },
+    ScriptCancelWait,
+
+    HealthCheck {
+        start_time: std::time::SystemTime,
+    },
+    HealthChecked {
+        start_time: std::time::SystemTime,
+        healthy: bool,
+    },
+
+    /// Open the script editor, filled with the current script
+    EditScript,
+    ScriptEdited(iced::widget::text_editor::Action),
+    /// Use the edited script from the next run on
+    SaveScript,
+    CancelEditScript,
 
-    _Stdin(String),
+    SetStdinInput(String),
+    Stdin(String),
     Stdout(String),
     Stderr(String),
 
     Activity(ActivityLight, activity::Message),
 
     SetShowLogs(bool),
-    SetForever(bool),
+    /// Handled by the app, which owns the log pane
+    SetSolo(bool),
+    /// Handled by the app, which owns the logs
+    ClearLogs,
+    SetRestart(RestartPolicy),
 }
 
 impl Runner {
     pub fn new(name: String, script: String) -> Runner {
         Runner {
             name,
+            description: None,
+            tags: Vec::new(),
+            auto_start: false,
+            depends_on: Vec::new(),
+            waiting: false,
             script,
-            forever: false,
+            command: None,
+            start_delay: None,
+            restart: RestartPolicy::Never,
+            stopped_for_good: false,
+            backoff_base: DEFAULT_BACKOFF_BASE,
+            backoff_cap: DEFAULT_BACKOFF_CAP,
+            consecutive_failures: 0,
+            max_restarts: None,
+            restarts: 0,
+            runs: 0,
+            kill_timeout: std::time::Duration::from_millis(DEFAULT_KILL_TIMEOUT_MS),
+            new_session: false,
+            env: std::collections::BTreeMap::new(),
+            env_clear: false,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            encoding: encoding_rs::UTF_8,
             status: Status::Off,
+            succeeded: false,
             stdout_activity: activity::Activity::new(iced::Color::from_rgb(0.0, 1.0, 0.0)),
             stderr_activity: activity::Activity::new(iced::Color::from_rgb(1.0, 1.0, 0.0)),
+            stdin_activity: activity::Activity::new(iced::Color::from_rgb(0.0, 0.5, 1.0)),
+            stdin_input: String::new(),
+            script_editor: None,
             show_logs: false,
+            max_log_lines: None,
+            max_line_len: DEFAULT_MAX_LINE_LEN,
+            strip_ansi: false,
+            collapse_repeats: false,
+            health_check: None,
+            healthy: None,
+            ready_when: None,
+            saw_ready_line: false,
+            print_output: true,
+            notify_on_complete: false,
+            confirm_kill: false,
+            color: None,
+            usage: None,
+            watch: Vec::new(),
+            watch_always: false,
+            watch_debounce: crate::watch::DEFAULT_DEBOUNCE,
+            rerun: false,
+            timeout: None,
+            schedule: None,
+            stall_timeout: None,
+            notify_on_stall: false,
+        }
+    }
+
+    /// When the current run started, if running
+    pub fn start_time(&self) -> Option<std::time::SystemTime> {
+        match self.status {
+            Status::Running { start_time, .. } => Some(start_time),
+            _ => None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.status, Status::Running { .. })
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(self.status, Status::Running { paused: true, .. })
+    }
+
+    /// Whether the last run was killed for going on past its `timeout`
+    pub fn timed_out(&self) -> bool {
+        matches!(
+            self.status,
+            Status::Completed {
+                timed_out: true,
+                ..
+            }
+        )
+    }
+
+    /// The status as one word, for reporting to other tools
+    #[cfg(feature = "api")]
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            Status::Off if self.waiting => "waiting",
+            Status::Off => "off",
+            Status::Delayed { .. } => "delayed",
+            Status::Running { paused: true, .. } => "paused",
+            Status::Running { .. } => "running",
+            Status::Completed {
+                timed_out: true, ..
+            } => "timed_out",
+            Status::Completed { status: 0, .. } => "succeeded",
+            Status::Completed { .. } => "failed",
+            Status::Failed { .. } => "gave up",
+        }
+    }
+
+    /// PID of the `run` wrapper, if running
+    pub fn pid(&self) -> Option<i32> {
+        match self.status {
+            Status::Running { pid, .. } => pid,
+            _ => None,
+        }
+    }
+
+    /// Whether the script is running, or about to be started or restarted
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status,
+            Status::Delayed { .. } | Status::Running { .. } | Status::Completed { .. }
+        )
+    }
+
+    /// Whether the run button would start the script, rather than stop
+    /// it or cancel its pending start
+    pub fn can_run(&self) -> bool {
+        !self.waiting
+            && matches!(
+                self.status,
+                Status::Off | Status::Completed { .. } | Status::Failed { .. }
+            )
+    }
+
+    /// The message that kills the script, or cancels its pending start
+    pub fn stop_message(&self) -> Option<Message> {
+        match self.status {
+            Status::Delayed { start_time } | Status::Running { start_time, .. } => {
+                Some(Message::ScriptKill { start_time })
+            }
+            _ if self.waiting => Some(Message::ScriptCancelWait),
+            _ => None,
+        }
+    }
+
+    /// Whether the last run exited with an error, or restarting gave up
+    pub fn failed(&self) -> bool {
+        matches!(
+            self.status,
+            Status::Completed { status, .. } | Status::Failed { status } if status != 0
+        )
+    }
+
+    /// Whether the last run exited successfully
+    pub fn succeeded(&self) -> bool {
+        self.succeeded
+    }
+
+    /// Stop for good, killing the script if it's running and cancelling
+    /// any pending start or restart
+    pub fn shut_down(&mut self) -> iced::Task<Message> {
+        self.stopped_for_good = true;
+        self.waiting = false;
+        match self.status {
+            Status::Delayed { start_time } | Status::Running { start_time, .. } => {
+                self.update(Message::ScriptKill { start_time })
+            }
+            _ => iced::Task::none(),
+        }
+    }
+
+    /// Forget past failures, so the next restart isn't delayed by them
+    pub fn reset_backoff(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Start the run and restart counts afresh, unless a run is going
+    pub fn reset_run_counts(&mut self) {
+        if matches!(
+            self.status,
+            Status::Off | Status::Completed { .. } | Status::Failed { .. }
+        ) {
+            self.restarts = 0;
+            self.runs = 0;
+        }
+    }
+
+    /// Restart delay after `consecutive_failures` failed runs:
+    /// `min(base * 2^(failures - 1), cap)`, or nothing before any failure
+    fn backoff(&self) -> std::time::Duration {
+        match self.consecutive_failures {
+            0 => std::time::Duration::ZERO,
+            n => self
+                .backoff_base
+                .saturating_mul(2u32.saturating_pow(n - 1))
+                .min(self.backoff_cap),
+        }
+    }
+
+    /// Whether any activity light is fading, and so needs redrawing
+    pub fn is_animating(&self) -> bool {
+        [
+            &self.stdout_activity,
+            &self.stderr_activity,
+            &self.stdin_activity,
+        ]
+        .iter()
+        .any(|activity| activity.is_lit())
+    }
+
+    /// Change how long the activity lights stay on and off, leaving
+    /// either at its default if not given
+    pub fn set_activity_lengths(
+        &mut self,
+        on_len: Option<std::time::Duration>,
+        off_len: Option<std::time::Duration>,
+    ) {
+        for activity in [
+            &mut self.stdout_activity,
+            &mut self.stderr_activity,
+            &mut self.stdin_activity,
+        ] {
+            activity.on_len = on_len.unwrap_or(activity::DEFAULT_ON_LEN);
+            activity.off_len = off_len.unwrap_or(activity::DEFAULT_OFF_LEN);
         }
     }
 
-    pub fn view(&self) -> iced::Element<'_, Message> {
+    /// Whether runners depending on this one may start: it's running and
+    /// has printed its `ready_when` line, or its last run completed
+    /// successfully
+    pub fn is_ready(&self) -> bool {
+        (self.is_running() && (self.ready_when.is_none() || self.saw_ready_line)) || self.succeeded
+    }
+
+    /// Check a stdout line against `ready_when`.
+    /// Returns whether this line made the runner ready.
+    pub fn check_ready_line(&mut self, line: &str) -> bool {
+        match &self.ready_when {
+            Some(ready_when) if !self.saw_ready_line && ready_when.is_match(line) => {
+                tracing::debug!(runner = %self.name, "saw ready line");
+                self.saw_ready_line = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The runner's row of controls. `solo` is whether the log pane shows
+    /// only this runner, a `stalled` runner is marked as such, and a
+    /// running one shows its recent `output_rate`.
+    pub fn view<'a>(
+        &'a self,
+        solo: bool,
+        stalled: bool,
+        output_rate: &'a output_rate::OutputRate,
+    ) -> iced::Element<'a, Message> {
         use iced::widget;
 
         let run_button = match self.status {
-            Status::Off => {
-                widget::button(icon::to_text(icon::Nerd::PlayOne)).on_press(Message::ScriptRun)
+            Status::Off | Status::Completed { .. } | Status::Failed { .. } if self.waiting => {
+                widget::button(icon::to_text(icon::Nerd::TimerSand))
+                    .on_press(Message::ScriptCancelWait)
+                    .style(widget::button::secondary)
             }
+            Status::Off => widget::button(icon::to_text(icon::Nerd::PlayOne))
+                .on_press(Message::ScriptRunManual),
+            Status::Delayed { start_time } => widget::button(icon::to_text(icon::Nerd::TimerSand))
+                .on_press(Message::ScriptKill { start_time })
+                .style(widget::button::secondary),
             Status::Running { start_time, .. } => widget::button(icon::to_text(icon::Nerd::Stop))
-                .on_press(Message::ScriptKill { start_time }),
+                .on_press(if self.confirm_kill {
+                    Message::ConfirmKill { start_time }
+                } else {
+                    Message::ScriptKill { start_time }
+                }),
+            Status::Completed {
+                signal: Some(signal),
+                timed_out: false,
+                ..
+            } => widget::button(widget::text(describe_signal(signal).0))
+                .on_press(Message::ScriptRunManual)
+                .style(widget::button::danger),
+            Status::Completed {
+                status,
+                timed_out: true,
+                ..
+            } => widget::button(
+                widget::row![
+                    icon::to_text(icon::Nerd::TimerAlert),
+                    widget::text(status.to_string())
+                ]
+                .spacing(5),
+            )
+            .on_press(Message::ScriptRunManual)
+            .style(widget::button::danger),
             Status::Completed { status, .. } => widget::button(widget::text(status.to_string()))
-                .on_press(Message::ScriptRun)
+                .on_press(Message::ScriptRunManual)
                 .style(if status == 0 {
                     widget::button::success
                 } else {
                     widget::button::danger
                 }),
+            Status::Failed { status } => widget::button(
+                widget::row![
+                    icon::to_text(icon::Nerd::AlertOctagon),
+                    widget::text(status.to_string())
+                ]
+                .spacing(5),
+            )
+            .on_press(Message::ScriptRunManual)
+            .style(widget::button::danger),
+        };
+
+        // Signal deaths are labelled in a word, with the signal on hover
+        let run_button: iced::Element<'_, Message> = match self.status {
+            Status::Completed {
+                signal: Some(signal),
+                ..
+            } if !self.waiting => {
+                let (reason, name) = describe_signal(signal);
+                widget::tooltip(
+                    run_button,
+                    widget::text(format!("{reason} ({name})")),
+                    widget::tooltip::Position::Bottom,
+                )
+                .into()
+            }
+            _ => run_button.into(),
         };
 
+        let now = std::time::SystemTime::now();
         let activity_stdout = self
             .stdout_activity
-            .view()
+            .view(now)
             .map(|msg| Message::Activity(ActivityLight::Stdout, msg));
         let activity_stderr = self
             .stderr_activity
-            .view()
+            .view(now)
             .map(|msg| Message::Activity(ActivityLight::Stderr, msg));
-        let activity = widget::column![activity_stdout, activity_stderr];
+        let activity_stdin = self
+            .stdin_activity
+            .view(now)
+            .map(|msg| Message::Activity(ActivityLight::Stdin, msg));
+        let activity = widget::column![activity_stdout, activity_stderr, activity_stdin];
 
-        let forever_button = if self.forever {
-            widget::button(crate::icon::to_text(crate::icon::Nerd::RepeatOne))
-                .on_press(Message::SetForever(false))
-                .style(widget::button::success)
-        } else {
-            widget::button(crate::icon::to_text(crate::icon::Nerd::RepeatOne))
-                .on_press(Message::SetForever(true))
-                .style(widget::button::secondary)
-        };
+        let restart_button = match self.restart {
+            RestartPolicy::Never => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::RepeatOne))
+                    .style(widget::button::secondary)
+            }
+            RestartPolicy::Always => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::RepeatOne))
+                    .style(widget::button::success)
+            }
+            RestartPolicy::OnFailure => {
+                widget::button(crate::icon::to_text(crate::icon::Nerd::RepeatOnce))
+                    .style(widget::button::success)
+            }
+        }
+        .on_press(Message::SetRestart(self.restart.next()));
 
         let logs_button = if self.show_logs {
             widget::button(crate::icon::to_text(crate::icon::Nerd::TextBoxOutline))
@@ -118,13 +754,183 @@ impl Runner {
                 .style(widget::button::secondary)
         };
 
-        widget::column![
-            widget::text(&self.name),
-            widget::row![run_button, activity, forever_button, logs_button]
+        let solo_button = widget::button(icon::to_text(icon::Nerd::Target))
+            .on_press(Message::SetSolo(!solo))
+            .style(if solo {
+                widget::button::success
+            } else {
+                widget::button::secondary
+            });
+
+        let clear_logs_button = widget::button(icon::to_text(icon::Nerd::Broom))
+            .on_press(Message::ClearLogs)
+            .style(widget::button::secondary);
+
+        // Commands aren't scripts, so there's nothing to edit
+        let edit_button = self.command.is_none().then(|| {
+            widget::button(icon::to_text(icon::Nerd::Pencil))
+                .on_press_maybe(self.script_editor.is_none().then_some(Message::EditScript))
+                .style(if self.script_editor.is_some() {
+                    widget::button::success
+                } else {
+                    widget::button::secondary
+                })
+        });
+
+        let mut column = widget::column![widget::text(&self.name)];
+        if let Some(description) = &self.description {
+            column = column.push(
+                widget::text(description)
+                    .size(12)
+                    .style(widget::text::secondary),
+            );
+        }
+        let pause_button = match self.status {
+            Status::Running {
+                start_time,
+                pid: Some(_),
+                paused,
+                ..
+            } if CAN_PAUSE => Some(if paused {
+                widget::button(icon::to_text(icon::Nerd::PlayOne))
+                    .on_press(Message::ScriptResume { start_time })
+                    .style(widget::button::success)
+            } else {
+                widget::button(icon::to_text(icon::Nerd::Pause))
+                    .on_press(Message::ScriptPause { start_time })
+                    .style(widget::button::secondary)
+            }),
+            _ => None,
+        };
+
+        let runtime_text = match self.status {
+            Status::Running { start_time, .. } => Some(format!(
+                "running for {}",
+                format_duration(start_time.elapsed().unwrap_or_default())
+            )),
+            Status::Completed {
+                start_time,
+                end_time,
+                ..
+            } => Some(format!(
+                "ran for {}",
+                format_duration(end_time.duration_since(start_time).unwrap_or_default())
+            )),
+            _ => None,
+        }
+        .map(|runtime| {
+            widget::text(runtime)
+                .size(12)
+                .font(iced::Font::MONOSPACE)
+                .style(widget::text::secondary)
+        });
+
+        let runs_text = (self.runs > 0).then(|| {
+            widget::text(format!("\u{d7}{}", self.runs))
+                .size(12)
+                .style(widget::text::secondary)
+        });
+
+        let sparkline = self.is_running().then(|| {
+            widget::canvas(output_rate)
+                .width(iced::Length::Fixed(output_rate::BUCKETS as f32))
+                .height(iced::Length::Fixed(16.0))
+        });
+
+        let usage_text = match (&self.status, self.usage) {
+            (Status::Running { .. }, Some(usage)) => Some(
+                widget::text(usage.to_short_string())
+                    .size(12)
+                    .font(iced::Font::MONOSPACE)
+                    .style(widget::text::secondary),
+            ),
+            _ => None,
+        };
+
+        let health_light = match (&self.status, &self.health_check) {
+            (Status::Running { .. }, Some(_)) => Some(match self.healthy {
+                None => icon::to_text(icon::Nerd::HeartOutline).style(widget::text::secondary),
+                Some(true) => icon::to_text(icon::Nerd::Heart).style(widget::text::success),
+                Some(false) => icon::to_text(icon::Nerd::HeartBroken).style(widget::text::danger),
+            }),
+            _ => None,
+        };
+
+        let stalled_light = stalled.then(|| {
+            widget::tooltip(
+                icon::to_text(icon::Nerd::Sleep).style(widget::text::danger),
+                widget::text("stalled"),
+                widget::tooltip::Position::Bottom,
+            )
+        });
+
+        let pid_text = match self.status {
+            Status::Running { pid: Some(pid), .. } => Some(
+                widget::text(format!("pid {pid}"))
+                    .size(12)
+                    .font(iced::Font::MONOSPACE)
+                    .style(widget::text::secondary),
+            ),
+            _ => None,
+        };
+
+        let signal_menu = match self.status {
+            Status::Running { pid: Some(_), .. } if !SIGNALS.is_empty() => Some(
+                widget::pick_list(SIGNALS, None::<Signal>, |signal| {
+                    Message::ScriptSignal(signal.raw)
+                })
+                .placeholder("signal"),
+            ),
+            _ => None,
+        };
+
+        column = column.push(
+            widget::row![run_button]
+                .push_maybe(runs_text)
+                .push_maybe(runtime_text)
+                .push_maybe(pid_text)
+                .push_maybe(usage_text)
+                .push_maybe(pause_button)
+                .push_maybe(signal_menu)
+                .push(activity)
+                .push_maybe(sparkline)
+                .push_maybe(stalled_light)
+                .push_maybe(health_light)
+                .push(restart_button)
+                .push(logs_button)
+                .push(solo_button)
+                .push(clear_logs_button)
+                .push_maybe(edit_button)
                 .align_y(iced::Alignment::Center)
                 .spacing(5),
-        ]
-        .into()
+        );
+        if let Some(content) = &self.script_editor {
+            column = column.push(
+                widget::text_editor(content)
+                    .on_action(Message::ScriptEdited)
+                    .font(iced::Font::MONOSPACE),
+            );
+            column = column.push(
+                widget::row![
+                    widget::button(icon::to_text(icon::Nerd::Check))
+                        .on_press(Message::SaveScript)
+                        .style(widget::button::success),
+                    widget::button(icon::to_text(icon::Nerd::Close))
+                        .on_press(Message::CancelEditScript)
+                        .style(widget::button::secondary),
+                ]
+                .spacing(5),
+            );
+        }
+        if let Status::Running { .. } = self.status {
+            column = column.push(
+                widget::text_input("stdin", &self.stdin_input)
+                    .on_input(Message::SetStdinInput)
+                    .on_submit(Message::Stdin(self.stdin_input.clone()))
+                    .font(iced::Font::MONOSPACE),
+            );
+        }
+        column.into()
     }
 
     pub fn update(&mut self, message: Message) -> iced::Task<Message> {
@@ -134,140 +940,332 @@ impl Runner {
                 ..
             } => match self.status {
                 Status::Completed {
+                    status,
                     start_time: status_start_time,
                     ..
                 } => {
                     if status_start_time == target_start_time {
                         self.status = Status::Off;
-                        if self.forever {
-                            iced::Task::done(Message::ScriptRun)
-                        } else {
+                        if self.stopped_for_good || !self.restart.should_restart(status) {
+                            iced::Task::none()
+                        } else if self.max_restarts.is_some_and(|max| self.restarts >= max) {
+                            tracing::warn!(
+                                runner = %self.name,
+                                "giving up after {} restarts",
+                                self.restarts
+                            );
+                            if status != 0 {
+                                self.status = Status::Failed { status };
+                            }
                             iced::Task::none()
+                        } else {
+                            self.restarts += 1;
+                            let delay = self.backoff() + self.start_delay.unwrap_or_default();
+                            self.run_after(delay)
                         }
                     } else {
-                        println!("[{}][<ClearStatus>] start_time mismatched", self.name);
+                        tracing::debug!(runner = %self.name, "clear status start_time mismatched");
                         iced::Task::none()
                     }
                 }
                 _ => {
-                    println!(
-                        "[{}][<ClearStatus>] script not in completed state",
-                        self.name
-                    );
+                    tracing::debug!(runner = %self.name, "clear status when not completed");
                     iced::Task::none()
                 }
             },
 
+            Message::ScriptCancelWait => {
+                self.waiting = false;
+                iced::Task::none()
+            }
+
+            Message::HealthCheck {
+                start_time: target_start_time,
+            } => match (&self.status, &self.health_check) {
+                (
+                    Status::Running {
+                        start_time, paused, ..
+                    },
+                    Some(health_check),
+                ) if *start_time == target_start_time => {
+                    if *paused {
+                        // A paused script can't answer, so wait until resumed
+                        Runner::schedule_health_check(target_start_time)
+                    } else {
+                        let health_check = health_check.clone();
+                        iced::Task::perform(Runner::check_health(health_check), move |healthy| {
+                            Message::HealthChecked {
+                                start_time: target_start_time,
+                                healthy,
+                            }
+                        })
+                    }
+                }
+                _ => iced::Task::none(),
+            },
+
+            Message::HealthChecked {
+                start_time: target_start_time,
+                healthy,
+            } => match self.status {
+                Status::Running { start_time, .. } if start_time == target_start_time => {
+                    if self.healthy != Some(healthy) {
+                        tracing::info!(
+                            runner = %self.name,
+                            "health check {}",
+                            if healthy { "passing" } else { "failing" }
+                        );
+                    }
+                    self.healthy = Some(healthy);
+                    Runner::schedule_health_check(target_start_time)
+                }
+                _ => iced::Task::none(),
+            },
+
+            Message::ScriptRunManual => {
+                self.reset_run_counts();
+                self.update(Message::ScriptRun)
+            }
+
             Message::ScriptRun => match self.status {
-                Status::Off | Status::Completed { .. } => {
-                    println!("[{}][<Run>] Running task", self.name);
-
-                    let (stdin_tx, stdin_rx) = mpsc::channel(1024);
-                    let (stdout_tx, stdout_rx) = mpsc::channel(1024);
-                    let (stderr_tx, stderr_rx) = mpsc::channel(1024);
-                    let (kill_tx, kill_rx) = oneshot::channel();
-
-                    let start_time = std::time::SystemTime::now();
-                    self.status = Status::Running {
-                        start_time,
-                        stdin_tx,
-                        kill_tx: Some(kill_tx),
-                    };
-                    let stdout_stream = ReceiverStream::new(stdout_rx);
-                    let stderr_stream = ReceiverStream::new(stderr_rx);
-
-                    iced::Task::batch([
-                        iced::Task::perform(
-                            Runner::exec(
-                                self.name.clone(),
-                                self.script.clone(),
-                                stdin_rx,
-                                stdout_tx,
-                                stderr_tx,
-                                kill_rx,
-                            ),
-                            move |status| Message::ScriptComplete {
-                                status,
-                                start_time,
-                                end_time: std::time::SystemTime::now(),
-                            },
-                        ),
-                        iced::Task::run(stdout_stream, Message::Stdout),
-                        iced::Task::run(stderr_stream, Message::Stderr),
-                    ])
+                Status::Off | Status::Completed { .. } | Status::Failed { .. } => {
+                    self.run_after(self.start_delay.unwrap_or_default())
                 }
                 _ => {
-                    println!("[{}][<Run>] already running", self.name);
+                    tracing::debug!(runner = %self.name, "already running");
                     iced::Task::none()
                 }
             },
 
+            Message::ScriptDelayElapsed {
+                start_time: target_start_time,
+            } => match self.status {
+                Status::Delayed { start_time } if start_time == target_start_time => self.spawn(),
+                _ => {
+                    tracing::debug!(runner = %self.name, "delay was cancelled");
+                    iced::Task::none()
+                }
+            },
+
+            Message::ScriptStarted {
+                start_time: target_start_time,
+                pid: child_pid,
+            } => {
+                if let Status::Running {
+                    start_time, pid, ..
+                } = &mut self.status
+                    && *start_time == target_start_time
+                {
+                    *pid = Some(child_pid);
+                }
+                iced::Task::none()
+            }
+
+            Message::ScriptPause {
+                start_time: target_start_time,
+            } => self.set_paused(target_start_time, true),
+            Message::ScriptResume {
+                start_time: target_start_time,
+            } => self.set_paused(target_start_time, false),
+
+            Message::ScriptSignal(signal) => {
+                match self.status {
+                    Status::Running { pid: Some(pid), .. } => {
+                        tracing::debug!(runner = %self.name, "sending signal {signal}");
+                        send_signal(pid, signal);
+                    }
+                    _ => {
+                        tracing::debug!(runner = %self.name, "not running, can't signal");
+                    }
+                }
+                iced::Task::none()
+            }
+
             Message::ScriptKill {
                 start_time: target_start_time,
             } => match &mut self.status {
+                Status::Delayed { start_time } => {
+                    if *start_time == target_start_time {
+                        tracing::debug!(runner = %self.name, "cancelling delayed start");
+                        self.status = Status::Off;
+                    }
+                    iced::Task::none()
+                }
                 Status::Running {
                     start_time,
+                    pid,
+                    paused,
                     kill_tx,
+                    timeout,
                     ..
                 } => {
                     if *start_time == target_start_time
                         && let Some(kill_tx) = kill_tx.take()
                     {
+                        // Aborts the wait for the timeout
+                        *timeout = None;
+                        // A stopped process won't act on SIGTERM until continued
+                        if *paused && let Some(pid) = *pid {
+                            set_stopped(pid, false);
+                            *paused = false;
+                        }
                         let _ = kill_tx.send(());
                     }
                     iced::Task::none()
                 }
                 _ => {
-                    println!("[{}][<Kill>] not running", self.name);
+                    tracing::debug!(runner = %self.name, "not running, can't kill");
+                    iced::Task::none()
+                }
+            },
+
+            Message::ScriptRestart => match self.status {
+                Status::Running { start_time, .. } => {
+                    self.rerun = true;
+                    self.update(Message::ScriptKill { start_time })
+                }
+                _ => {
+                    tracing::debug!(runner = %self.name, "not running, can't restart");
                     iced::Task::none()
                 }
             },
 
+            Message::ScriptTimedOut {
+                start_time: target_start_time,
+            } => match &mut self.status {
+                Status::Running {
+                    start_time,
+                    timed_out,
+                    ..
+                } if *start_time == target_start_time => {
+                    tracing::warn!(
+                        runner = %self.name,
+                        "timed out after {}, stopping",
+                        format_duration(self.timeout.unwrap_or_default())
+                    );
+                    *timed_out = true;
+                    self.update(Message::ScriptKill {
+                        start_time: target_start_time,
+                    })
+                }
+                _ => iced::Task::none(),
+            },
+
             Message::ScriptComplete {
                 status,
+                signal,
                 start_time,
                 end_time,
             } => {
-                println!("[{}][<Complete>] status {status}", self.name);
+                match signal {
+                    Some(signal) => {
+                        let (reason, name) = describe_signal(signal);
+                        tracing::info!(runner = %self.name, "{reason} ({name})");
+                    }
+                    None => tracing::info!(runner = %self.name, "exited with status {status}"),
+                }
+                let timed_out = matches!(
+                    self.status,
+                    Status::Running {
+                        timed_out: true,
+                        ..
+                    }
+                );
+                self.succeeded = status == 0;
+
+                let runtime = end_time.duration_since(start_time).unwrap_or_default();
+                if self.succeeded || runtime >= BACKOFF_RESET_AFTER {
+                    self.consecutive_failures = 0;
+                } else {
+                    self.consecutive_failures += 1;
+                }
 
                 self.status = Status::Completed {
                     status,
+                    signal,
                     start_time,
-                    _end_time: end_time,
+                    end_time,
+                    timed_out,
                 };
 
-                iced::Task::future(async move {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-                    Message::ScriptClearStatus { start_time }
-                })
+                if std::mem::take(&mut self.rerun) {
+                    return iced::Task::done(Message::ScriptRun);
+                }
+                iced::Task::future(async move {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+                    Message::ScriptClearStatus { start_time }
+                })
+            }
+
+            Message::EditScript => {
+                self.script_editor =
+                    Some(iced::widget::text_editor::Content::with_text(&self.script));
+                iced::Task::none()
+            }
+            Message::ScriptEdited(action) => {
+                if let Some(content) = &mut self.script_editor {
+                    content.perform(action);
+                }
+                iced::Task::none()
+            }
+            Message::SaveScript => {
+                if let Some(content) = self.script_editor.take() {
+                    let mut script = content.text();
+                    // The editor always ends the text with a newline
+                    if !self.script.ends_with('\n') {
+                        script.pop();
+                    }
+                    tracing::info!(runner = %self.name, "script edited");
+                    self.script = script;
+                }
+                iced::Task::none()
+            }
+            Message::CancelEditScript => {
+                self.script_editor = None;
+                iced::Task::none()
+            }
+
+            Message::SetStdinInput(s) => {
+                self.stdin_input = s;
+                iced::Task::none()
             }
 
-            Message::_Stdin(s) => match &self.status {
+            Message::Stdin(s) => match &self.status {
                 Status::Running { stdin_tx, .. } => {
+                    self.stdin_input.clear();
                     let name = self.name.clone();
                     let stdin_tx = stdin_tx.clone();
-                    iced::Task::future(async move {
+                    let send_task = iced::Task::future(async move {
                         if let Err(err) = stdin_tx.send(s).await {
-                            println!("[{name}][<Stdin>] {err:?}");
+                            tracing::warn!(runner = %name, "unable to send stdin: {err:?}");
                         }
                     })
-                    .discard()
+                    .discard();
+                    let activity_task = self
+                        .stdin_activity
+                        .trigger()
+                        .map(|msg| Message::Activity(ActivityLight::Stdin, msg));
+                    iced::Task::batch([send_task, activity_task])
                 }
                 _ => {
-                    println!("[{}][<Stdin>] task not running", self.name);
+                    tracing::debug!(runner = %self.name, "not running, can't send stdin");
                     iced::Task::none()
                 }
             },
 
             Message::Stdout(s) => {
-                println!("[{}][>] {s}", self.name);
+                if self.print_output {
+                    tracing::trace!(runner = %self.name, stdout = s);
+                }
 
                 self.stdout_activity
                     .trigger()
                     .map(|msg| Message::Activity(ActivityLight::Stdout, msg))
             }
             Message::Stderr(s) => {
-                println!("[{}][!] {s}", self.name);
+                if self.print_output {
+                    tracing::trace!(runner = %self.name, stderr = s);
+                }
 
                 self.stderr_activity
                     .trigger()
@@ -282,139 +1280,417 @@ impl Runner {
                 .stderr_activity
                 .update(message)
                 .map(|msg| Message::Activity(ActivityLight::Stderr, msg)),
+            Message::Activity(ActivityLight::Stdin, message) => self
+                .stdin_activity
+                .update(message)
+                .map(|msg| Message::Activity(ActivityLight::Stdin, msg)),
 
             Message::SetShowLogs(v) => {
                 self.show_logs = v;
                 iced::Task::none()
             }
-            Message::SetForever(v) => {
-                self.forever = v;
+            Message::SetSolo(_) | Message::ClearLogs | Message::ConfirmKill { .. } => {
+                iced::Task::none()
+            }
+            Message::SetRestart(v) => {
+                self.restart = v;
+                iced::Task::none()
+            }
+        }
+    }
+
+    /// Pause or resume the running script. The `run` wrapper turns SIGTSTP
+    /// into a SIGSTOP for the script's process group, and forwards SIGCONT.
+    fn set_paused(
+        &mut self,
+        target_start_time: std::time::SystemTime,
+        pause: bool,
+    ) -> iced::Task<Message> {
+        match &mut self.status {
+            Status::Running {
+                start_time,
+                pid: Some(pid),
+                paused,
+                ..
+            } if *start_time == target_start_time => {
+                set_stopped(*pid, pause);
+                *paused = pause;
+            }
+            _ => {
+                tracing::debug!(runner = %self.name, "not running, can't pause");
+            }
+        }
+        iced::Task::none()
+    }
+
+    /// Spawn the script after `delay`, waiting in `Status::Delayed`
+    fn run_after(&mut self, delay: std::time::Duration) -> iced::Task<Message> {
+        if delay.is_zero() {
+            return self.spawn();
+        }
+
+        tracing::debug!(runner = %self.name, "delaying start by {delay:?}");
+        self.succeeded = false;
+
+        let start_time = std::time::SystemTime::now();
+        self.status = Status::Delayed { start_time };
+        iced::Task::future(async move {
+            tokio::time::sleep(delay).await;
+            Message::ScriptDelayElapsed { start_time }
+        })
+    }
+
+    /// Spawn the script, moving to `Status::Running`
+    fn spawn(&mut self) -> iced::Task<Message> {
+        tracing::debug!(runner = %self.name, "starting");
+        self.succeeded = false;
+        self.runs += 1;
+        self.healthy = None;
+        self.saw_ready_line = false;
+
+        let (stdin_tx, stdin_rx) = mpsc::channel(1024);
+        let (stdout_tx, stdout_rx) = mpsc::channel(1024);
+        let (stderr_tx, stderr_rx) = mpsc::channel(1024);
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let (pid_tx, pid_rx) = oneshot::channel();
+
+        let start_time = std::time::SystemTime::now();
+        let (timeout_task, timeout) = match self.timeout {
+            Some(timeout) => {
+                let (task, handle) = iced::Task::future(async move {
+                    tokio::time::sleep(timeout).await;
+                    Message::ScriptTimedOut { start_time }
+                })
+                .abortable();
+                (task, Some(handle.abort_on_drop()))
+            }
+            None => (iced::Task::none(), None),
+        };
+        self.status = Status::Running {
+            start_time,
+            pid: None,
+            paused: false,
+            stdin_tx,
+            kill_tx: Some(kill_tx),
+            timeout,
+            timed_out: false,
+        };
+        let stdout_stream = ReceiverStream::new(stdout_rx);
+        let stderr_stream = ReceiverStream::new(stderr_rx);
+
+        iced::Task::batch([
+            iced::Task::perform(
+                Runner::exec(
+                    self.name.clone(),
+                    ExecConfig {
+                        script: self.script.clone(),
+                        command: self.command.clone(),
+                        kill_timeout: self.kill_timeout,
+                        new_session: self.new_session,
+                        env: self.env.clone(),
+                        env_clear: self.env_clear,
+                        read_buffer_size: self.read_buffer_size,
+                        encoding: self.encoding,
+                    },
+                    stdin_rx,
+                    stdout_tx,
+                    stderr_tx,
+                    pid_tx,
+                    kill_rx,
+                ),
+                move |(status, signal)| Message::ScriptComplete {
+                    status,
+                    signal,
+                    start_time,
+                    end_time: std::time::SystemTime::now(),
+                },
+            ),
+            iced::Task::future(pid_rx)
+                .and_then(move |pid| iced::Task::done(Message::ScriptStarted { start_time, pid })),
+            iced::Task::run(stdout_stream, Message::Stdout),
+            iced::Task::run(stderr_stream, Message::Stderr),
+            timeout_task,
+            if self.health_check.is_some() {
+                Runner::schedule_health_check(start_time)
+            } else {
                 iced::Task::none()
+            },
+        ])
+    }
+
+    fn schedule_health_check(start_time: std::time::SystemTime) -> iced::Task<Message> {
+        iced::Task::future(async move {
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+            Message::HealthCheck { start_time }
+        })
+    }
+
+    /// Run `health_check` through the `run` wrapper, the same way scripts
+    /// are run, and report whether it exited successfully in time.
+    async fn check_health(health_check: String) -> bool {
+        let Ok(current_exe) = std::env::current_exe() else {
+            return false;
+        };
+
+        let mut command = tokio::process::Command::new(current_exe);
+        command.arg("run");
+        command.arg("-c");
+        command.arg(health_check);
+
+        command.stdin(std::process::Stdio::null());
+        command.stdout(std::process::Stdio::null());
+        command.stderr(std::process::Stdio::null());
+
+        let Ok(mut child) = command.spawn() else {
+            return false;
+        };
+        match tokio::time::timeout(HEALTH_CHECK_INTERVAL, child.wait()).await {
+            Ok(Ok(status)) => status.success(),
+            Ok(Err(_)) => false,
+            Err(_) => {
+                // Let the wrapper run its kill sequence, and reap it later
+                Runner::terminate(&mut child);
+                tokio::spawn(async move { child.wait().await });
+                false
             }
         }
     }
 
+    /// Ask the `run` wrapper `child` to stop its script. On Windows it's
+    /// killed outright, and its Job Object takes the script with it.
+    fn terminate(child: &mut tokio::process::Child) {
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            send_signal(pid as i32, libc::SIGTERM);
+        }
+        #[cfg(windows)]
+        let _ = child.start_kill();
+    }
+
+    /// Report that `exec` couldn't run the script, returning its status
+    async fn exec_failed(
+        name: &str,
+        stderr_tx: &mpsc::Sender<String>,
+        err: String,
+    ) -> (i32, Option<i32>) {
+        tracing::error!(runner = %name, "{err}");
+        // A whole line, so it's logged even if it arrives after completion
+        let _ = stderr_tx.send(format!("{err}\n")).await;
+        (99, None)
+    }
+
     async fn exec(
         name: String,
-        script: String,
-        _stdin_rx: mpsc::Receiver<String>,
+        config: ExecConfig,
+        mut stdin_rx: mpsc::Receiver<String>,
         stdout_tx: mpsc::Sender<String>,
         stderr_tx: mpsc::Sender<String>,
+        pid_tx: oneshot::Sender<i32>,
         kill_rx: oneshot::Receiver<()>,
-    ) -> i32 {
-        use tokio::io::AsyncReadExt;
-
-        println!("[{name}] ---- BEGIN ----");
+    ) -> (i32, Option<i32>) {
+        tracing::debug!(runner = %name, "spawning");
 
         let current_exe = match std::env::current_exe() {
             Ok(current_exe) => current_exe,
             Err(err) => {
                 let err = format!("Unable to find current exe: {err:?}");
-                println!("[{name}][!] {err}");
-                let _ = stderr_tx.send(err).await;
-                return 99;
+                return Runner::exec_failed(&name, &stderr_tx, err).await;
             }
         };
 
         let mut command = tokio::process::Command::new(current_exe);
         command.arg("run");
-        command.arg("-c");
-        command.arg(script);
+        command.arg("--kill-timeout-ms");
+        command.arg(config.kill_timeout.as_millis().to_string());
+        if config.new_session {
+            command.arg("--new-session");
+        }
+        match config.command {
+            Some(argv) => {
+                command.arg("--");
+                command.args(argv);
+            }
+            None => {
+                command.arg("-c");
+                command.arg(config.script);
+            }
+        }
+
+        // The run wrapper passes its environment on to the script
+        if config.env_clear {
+            command.env_clear();
+        }
+        command.envs(config.env);
 
         command.stdout(std::process::Stdio::piped());
         command.stderr(std::process::Stdio::piped());
         command.stdin(std::process::Stdio::piped());
 
-        let mut child = command.spawn().unwrap();
-        let child_pid = child.id().unwrap() as i32;
-
-        let Some(mut stdout) = child.stdout.take() else {
-            println!("[{name}] Error getting stdout");
-            return 99;
-        };
-        let Some(mut stderr) = child.stderr.take() else {
-            println!("[{name}] Error getting stderr");
-            return 99;
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                let err = format!("Unable to start script: {err}");
+                return Runner::exec_failed(&name, &stderr_tx, err).await;
+            }
         };
-        let Some(mut stdin) = child.stdin.take() else {
-            println!("[{name}] Error getting stdin");
-            return 99;
+        let Some(child_pid) = child.id() else {
+            let err = "Script exited before it started".to_string();
+            return Runner::exec_failed(&name, &stderr_tx, err).await;
         };
+        let child_pid = child_pid as i32;
+        let _ = pid_tx.send(child_pid);
 
-        use tokio::io::AsyncWriteExt;
-        let _ = stdin.shutdown().await;
+        let (Some(stdout), Some(stderr), Some(mut stdin)) =
+            (child.stdout.take(), child.stderr.take(), child.stdin.take())
+        else {
+            Runner::terminate(&mut child);
+            let err = "Unable to get the script's stdio".to_string();
+            return Runner::exec_failed(&name, &stderr_tx, err).await;
+        };
 
         let _name = name.clone();
-        let reading_stdout_handle = tokio::task::spawn(async move {
+        let writing_stdin_handle = tokio::task::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+
             let name = _name;
-            let mut stdout_open = true;
-            let mut stderr_open = true;
-            let mut stdout_buf = [0u8; 1024];
-            let mut stderr_buf = [0u8; 1024];
-            loop {
-                if !stdout_open && !stderr_open {
+            while let Some(s) = stdin_rx.recv().await {
+                let line = format!("{s}\n");
+                if let Err(e) = stdin.write_all(line.as_bytes()).await {
+                    tracing::warn!(runner = %name, "stdin io error: {e:?}");
                     break;
                 }
-                tokio::select! {
-                    n = stdout.read(&mut stdout_buf), if stdout_open => {
-                        match n {
-                            Ok(0) => {
-                                stdout_open = false;
-                            },
-                            Ok(n) => {
-                                let s = String::from_utf8_lossy(&stdout_buf[..n]).into_owned();
-                                let _ = stdout_tx.send(s).await;
-                                stdout_buf[0..n].fill(0);
-                            },
-                            Err(e) => {
-                                println!("[{name}][>][!] io error: {e:?}");
-                            }
-                        }
-                    },
-                    n = stderr.read(&mut stderr_buf), if stderr_open => {
-                        match n {
-                            Ok(0) => {
-                                stderr_open = false;
-                            },
-                            Ok(n) => {
-                                let s = String::from_utf8_lossy(&stderr_buf[..n]).into_owned();
-                                let _ = stderr_tx.send(s).await;
-                                stderr_buf[0..n].fill(0);
-                            },
-                            Err(e) => {
-                                println!("[{name}][!][!] io error: {e:?}");
-                            }
-                        }
-                    },
-                }
+                let _ = stdin.flush().await;
             }
         });
 
+        let reading_stdout_handle = tokio::task::spawn(Runner::read_output(
+            name.clone(),
+            config.read_buffer_size,
+            config.encoding,
+            stdout,
+            stderr,
+            stdout_tx,
+            stderr_tx,
+        ));
+
         tokio::select! {
             _ = child.wait() => {},
             _ = kill_rx => {
-                unsafe { libc::kill(child_pid, libc::SIGTERM) };
+                Runner::terminate(&mut child);
+
+                // The run wrapper escalates to SIGKILL itself after
+                // kill_timeout, only kill it if that doesn't work
+                tokio::select! {
+                    _ = child.wait() => {},
+                    _ = tokio::time::sleep(config.kill_timeout + KILL_TIMEOUT_MARGIN) => {
+                        tracing::warn!(runner = %name, "timed out, killing");
+                        let _ = child.start_kill();
+                    }
+                }
             }
         }
 
         let res = child.wait().await;
+        writing_stdin_handle.abort();
         let _ = reading_stdout_handle.await;
-        println!("[{name}] res {res:?}");
+        tracing::debug!(runner = %name, "exited: {res:?}");
 
-        println!("[{name}] ---- END ----");
+        match res {
+            Ok(res) if res.success() => (0, None),
+            #[cfg(unix)]
+            Ok(res) => (1, std::os::unix::process::ExitStatusExt::signal(&res)),
+            #[cfg(not(unix))]
+            Ok(_) => (1, None),
+            Err(_) => (1, None),
+        }
+    }
 
-        if let Ok(res) = res {
-            if res.success() { 0 } else { 1 }
-        } else {
-            1
+    /// Forward text read from `stdout` and `stderr` until both are closed.
+    /// Sending waits for room, so a script writing faster than we can keep
+    /// up with is slowed down rather than losing output. If the receivers
+    /// are gone, reading stops, and the pipes are closed.
+    async fn read_output(
+        name: String,
+        buf_size: usize,
+        encoding: &'static encoding_rs::Encoding,
+        mut stdout: impl tokio::io::AsyncRead + Unpin,
+        mut stderr: impl tokio::io::AsyncRead + Unpin,
+        stdout_tx: mpsc::Sender<String>,
+        stderr_tx: mpsc::Sender<String>,
+    ) {
+        use tokio::io::AsyncReadExt;
+
+        let mut stdout_open = true;
+        let mut stderr_open = true;
+        let mut stdout_buf = vec![0u8; buf_size];
+        let mut stderr_buf = vec![0u8; buf_size];
+        let mut stdout_decoder = decode::Decoder::new(encoding);
+        let mut stderr_decoder = decode::Decoder::new(encoding);
+        loop {
+            if !stdout_open && !stderr_open {
+                break;
+            }
+            tokio::select! {
+                n = stdout.read(&mut stdout_buf), if stdout_open => {
+                    match n {
+                        Ok(0) => {
+                            stdout_open = false;
+                            let s = stdout_decoder.finish();
+                            if !s.is_empty() && stdout_tx.send(s).await.is_err() {
+                                tracing::debug!(runner = %name, "stdout receiver closed");
+                                return;
+                            }
+                        },
+                        Ok(n) => {
+                            let s = stdout_decoder.decode(&stdout_buf[..n]);
+                            if !s.is_empty() && stdout_tx.send(s).await.is_err() {
+                                tracing::debug!(runner = %name, "stdout receiver closed");
+                                return;
+                            }
+                        },
+                        Err(e) => {
+                            tracing::warn!(runner = %name, "stdout io error: {e:?}");
+                        }
+                    }
+                },
+                n = stderr.read(&mut stderr_buf), if stderr_open => {
+                    match n {
+                        Ok(0) => {
+                            stderr_open = false;
+                            let s = stderr_decoder.finish();
+                            if !s.is_empty() && stderr_tx.send(s).await.is_err() {
+                                tracing::debug!(runner = %name, "stderr receiver closed");
+                                return;
+                            }
+                        },
+                        Ok(n) => {
+                            let s = stderr_decoder.decode(&stderr_buf[..n]);
+                            if !s.is_empty() && stderr_tx.send(s).await.is_err() {
+                                tracing::debug!(runner = %name, "stderr receiver closed");
+                                return;
+                            }
+                        },
+                        Err(e) => {
+                            tracing::warn!(runner = %name, "stderr io error: {e:?}");
+                        }
+                    }
+                },
+            }
         }
     }
 }
 
 mod activity {
+    pub const DEFAULT_ON_LEN: std::time::Duration = std::time::Duration::from_millis(100);
+    pub const DEFAULT_OFF_LEN: std::time::Duration = std::time::Duration::from_millis(50);
+
     pub struct Activity {
         state: State,
         color: iced::Color,
+        /// How long the light stays on after a trigger
+        pub on_len: std::time::Duration,
+        /// How long the light stays off before it can be triggered again
+        pub off_len: std::time::Duration,
     }
 
     enum State {
@@ -433,16 +1709,32 @@ mod activity {
             Activity {
                 state: State::Off(std::time::UNIX_EPOCH),
                 color,
+                on_len: DEFAULT_ON_LEN,
+                off_len: DEFAULT_OFF_LEN,
             }
         }
 
-        pub fn view(&self) -> iced::Element<'_, Message> {
-            let icon = match self.state {
-                State::On(_) => crate::icon::Nerd::SquareRounded,
-                State::Off(_) => crate::icon::Nerd::SquareRoundedOutline,
-            };
+        /// Whether the light is fading out, and so needs redrawing
+        pub fn is_lit(&self) -> bool {
+            matches!(self.state, State::On(_))
+        }
+
+        /// Draw the light, fading the fill out over `on_len` from when it
+        /// was triggered until `now`
+        pub fn view(&self, now: std::time::SystemTime) -> iced::Element<'_, Message> {
+            let outline = crate::icon::to_text(crate::icon::Nerd::SquareRoundedOutline);
 
-            crate::icon::to_text(icon).color(self.color).into()
+            match self.state {
+                State::On(t) => {
+                    let elapsed = now.duration_since(t).unwrap_or_default();
+                    let alpha =
+                        1.0 - elapsed.as_secs_f32() / self.on_len.as_secs_f32().max(f32::EPSILON);
+                    let fill = crate::icon::to_text(crate::icon::Nerd::SquareRounded)
+                        .color(self.color.scale_alpha(alpha.clamp(0.0, 1.0)));
+                    iced::widget::stack![outline.color(self.color), fill].into()
+                }
+                State::Off(_) => outline.color(self.color).into(),
+            }
         }
 
         pub fn trigger(&mut self) -> iced::Task<Message> {
@@ -450,8 +1742,8 @@ mod activity {
         }
 
         pub fn update(&mut self, message: Message) -> iced::Task<Message> {
-            let on_len = std::time::Duration::from_millis(100);
-            let off_len = std::time::Duration::from_millis(50);
+            let on_len = self.on_len;
+            let off_len = self.off_len;
             match message {
                 Message::Trigger => match &mut self.state {
                     State::Off(t) => {
@@ -482,3 +1774,552 @@ mod activity {
         }
     }
 }
+
+pub mod output_rate {
+    use std::collections::VecDeque;
+
+    /// Seconds of history kept
+    pub const BUCKETS: usize = 60;
+    const BUCKET_LEN: std::time::Duration = std::time::Duration::from_secs(1);
+
+    /// Lines logged in each of the last `BUCKETS` seconds, drawn as a
+    /// sparkline
+    pub struct OutputRate {
+        /// Oldest first, the last being the second in progress
+        counts: VecDeque<u32>,
+        /// When the second in progress began
+        bucket_start: std::time::Instant,
+    }
+
+    impl OutputRate {
+        pub fn new(now: std::time::Instant) -> OutputRate {
+            OutputRate {
+                counts: std::iter::repeat_n(0, BUCKETS).collect(),
+                bucket_start: now,
+            }
+        }
+
+        /// Move on to the second containing `now`, so quiet seconds show
+        /// as gaps
+        pub fn roll(&mut self, now: std::time::Instant) {
+            let elapsed = now.saturating_duration_since(self.bucket_start);
+            let buckets = (elapsed.as_secs() / BUCKET_LEN.as_secs()) as usize;
+            for _ in 0..buckets.min(BUCKETS) {
+                self.counts.pop_front();
+                self.counts.push_back(0);
+            }
+            self.bucket_start += BUCKET_LEN * buckets as u32;
+        }
+
+        pub fn record(&mut self, lines: u32, now: std::time::Instant) {
+            self.roll(now);
+            if let Some(count) = self.counts.back_mut() {
+                *count += lines;
+            }
+        }
+
+        #[cfg(test)]
+        pub fn counts(&self) -> &VecDeque<u32> {
+            &self.counts
+        }
+    }
+
+    impl<Message> iced::widget::canvas::Program<Message> for OutputRate {
+        type State = ();
+
+        fn draw(
+            &self,
+            _state: &(),
+            renderer: &iced::Renderer,
+            theme: &iced::Theme,
+            bounds: iced::Rectangle,
+            _cursor: iced::mouse::Cursor,
+        ) -> Vec<iced::widget::canvas::Geometry> {
+            let mut frame = iced::widget::canvas::Frame::new(renderer, bounds.size());
+            // Scaled to the busiest second shown
+            let max = self.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+            let bar_width = bounds.width / BUCKETS as f32;
+            let color = theme.extended_palette().primary.base.color;
+            for (i, &count) in self.counts.iter().enumerate() {
+                let height = bounds.height * count as f32 / max;
+                frame.fill_rectangle(
+                    iced::Point::new(i as f32 * bar_width, bounds.height - height),
+                    iced::Size::new(bar_width, height),
+                    color,
+                );
+            }
+            vec![frame.into_geometry()]
+        }
+    }
+}
+
+/// Format a duration as `HH:MM:SS`.
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+mod utf8 {
+    /// Decodes UTF-8 read in arbitrary chunks, holding back an incomplete
+    /// trailing sequence until the rest of it arrives
+    #[derive(Default)]
+    pub struct Decoder {
+        pending: Vec<u8>,
+    }
+
+    impl Decoder {
+        /// Decode complete code points, replacing invalid sequences with U+FFFD
+        pub fn decode(&mut self, bytes: &[u8]) -> String {
+            self.pending.extend_from_slice(bytes);
+
+            let mut s = String::with_capacity(self.pending.len());
+            let mut rest = &self.pending[..];
+            loop {
+                match std::str::from_utf8(rest) {
+                    Ok(valid) => {
+                        s.push_str(valid);
+                        rest = &[];
+                        break;
+                    }
+                    Err(e) => {
+                        let (valid, after) = rest.split_at(e.valid_up_to());
+                        // Can't fail, from_utf8 validated this prefix
+                        s.push_str(std::str::from_utf8(valid).unwrap());
+                        match e.error_len() {
+                            Some(len) => {
+                                s.push(char::REPLACEMENT_CHARACTER);
+                                rest = &after[len..];
+                            }
+                            None => {
+                                // Incomplete sequence at the end, keep it for later
+                                rest = after;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let n_pending = rest.len();
+            self.pending.drain(..self.pending.len() - n_pending);
+            s
+        }
+
+        /// Decode whatever is left at the end of the stream
+        pub fn finish(&mut self) -> String {
+            let s = String::from_utf8_lossy(&self.pending).into_owned();
+            self.pending.clear();
+            s
+        }
+    }
+}
+
+mod decode {
+    /// Decodes output read in arbitrary chunks into UTF-8. UTF-8 itself is
+    /// only checked, other encodings are converted by `encoding_rs`.
+    pub enum Decoder {
+        Utf8(super::utf8::Decoder),
+        Other(encoding_rs::Decoder),
+    }
+
+    impl Decoder {
+        pub fn new(encoding: &'static encoding_rs::Encoding) -> Decoder {
+            if encoding == encoding_rs::UTF_8 {
+                Decoder::Utf8(super::utf8::Decoder::default())
+            } else {
+                Decoder::Other(encoding.new_decoder_without_bom_handling())
+            }
+        }
+
+        /// Decode complete characters, holding back any that are split
+        pub fn decode(&mut self, bytes: &[u8]) -> String {
+            match self {
+                Decoder::Utf8(decoder) => decoder.decode(bytes),
+                Decoder::Other(decoder) => decode_to_string(decoder, bytes, false),
+            }
+        }
+
+        /// Decode whatever is left at the end of the stream
+        pub fn finish(&mut self) -> String {
+            match self {
+                Decoder::Utf8(decoder) => decoder.finish(),
+                Decoder::Other(decoder) => decode_to_string(decoder, &[], true),
+            }
+        }
+    }
+
+    fn decode_to_string(decoder: &mut encoding_rs::Decoder, bytes: &[u8], last: bool) -> String {
+        // Sized for the worst case, so everything is decoded in one go
+        let capacity = decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len() * 3 + 16);
+        let mut s = String::with_capacity(capacity);
+        let _ = decoder.decode_to_string(bytes, &mut s, last);
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn durations_are_formatted_as_clock_times() {
+        let format = |secs| format_duration(std::time::Duration::from_secs(secs));
+        assert_eq!(format(0), "00:00:00");
+        assert_eq!(format(192), "00:03:12");
+        assert_eq!(format(90061), "25:01:01");
+    }
+
+    #[test]
+    fn shutting_down_cancels_a_delayed_start() {
+        let mut runner = Runner::new("a".to_string(), "true".to_string());
+        runner.start_delay = Some(std::time::Duration::from_secs(60));
+        runner.restart = RestartPolicy::Always;
+        assert!(!runner.is_active());
+
+        let _ = runner.update(Message::ScriptRun);
+        assert!(runner.is_active());
+
+        let _ = runner.shut_down();
+        assert!(!runner.is_active());
+        assert_eq!(runner.restart, RestartPolicy::Always);
+        assert!(runner.stopped_for_good);
+    }
+
+    #[test]
+    fn output_rate_rolls_forward_each_second() {
+        use output_rate::{BUCKETS, OutputRate};
+
+        let start = std::time::Instant::now();
+        let secs = |secs: f32| start + std::time::Duration::from_secs_f32(secs);
+        let mut rate = OutputRate::new(start);
+        rate.record(2, secs(0.2));
+        rate.record(3, secs(0.9));
+        rate.record(1, secs(2.5));
+        let recent = rate
+            .counts()
+            .iter()
+            .skip(BUCKETS - 3)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(recent, [5, 0, 1]);
+
+        rate.roll(secs(1000.0));
+        assert_eq!(rate.counts().len(), BUCKETS);
+        assert!(rate.counts().iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn scripts_are_edited_until_saved() {
+        use iced::widget::text_editor::{Action, Edit, Motion};
+
+        let append = |runner: &mut Runner, text: &str| {
+            let _ = runner.update(Message::ScriptEdited(Action::Move(Motion::End)));
+            let paste = Edit::Paste(std::sync::Arc::new(text.to_string()));
+            let _ = runner.update(Message::ScriptEdited(Action::Edit(paste)));
+        };
+        let mut runner = Runner::new("a".to_string(), "echo a".to_string());
+        let _ = runner.update(Message::EditScript);
+        append(&mut runner, " b");
+        let _ = runner.update(Message::CancelEditScript);
+        assert_eq!(runner.script, "echo a");
+
+        let _ = runner.update(Message::EditScript);
+        append(&mut runner, " b");
+        let _ = runner.update(Message::SaveScript);
+        assert_eq!(runner.script, "echo a b");
+        assert!(runner.script_editor.is_none());
+    }
+
+    #[test]
+    fn restarting_kills_then_runs_again() {
+        let mut runner = Runner::new("a".to_string(), "sleep 60".to_string());
+        let _ = runner.update(Message::ScriptRestart);
+        assert!(!runner.rerun);
+
+        let _ = runner.update(Message::ScriptRun);
+        let start_time = runner.start_time().unwrap();
+        let _ = runner.update(Message::ScriptRestart);
+        assert!(runner.rerun);
+        assert!(matches!(
+            runner.status,
+            Status::Running { kill_tx: None, .. }
+        ));
+
+        let _ = runner.update(Message::ScriptComplete {
+            status: 143,
+            signal: None,
+            start_time,
+            end_time: std::time::SystemTime::now(),
+        });
+        assert!(!runner.rerun);
+    }
+
+    #[test]
+    fn only_manual_runs_reset_the_counts() {
+        let mut runner = Runner::new("a".to_string(), "false".to_string());
+        let complete = |runner: &mut Runner| {
+            let start_time = runner.start_time().unwrap();
+            let _ = runner.update(Message::ScriptComplete {
+                status: 1,
+                signal: None,
+                start_time,
+                end_time: std::time::SystemTime::now(),
+            });
+        };
+        let _ = runner.update(Message::ScriptRunManual);
+        complete(&mut runner);
+        runner.restarts = 3;
+
+        // As sent by schedules, watches and dependencies becoming ready
+        let _ = runner.update(Message::ScriptRun);
+        assert_eq!((runner.runs, runner.restarts), (2, 3));
+        complete(&mut runner);
+
+        let _ = runner.update(Message::ScriptRunManual);
+        assert_eq!((runner.runs, runner.restarts), (1, 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signal_deaths_are_described() {
+        assert_eq!(
+            describe_signal(libc::SIGSEGV),
+            ("segfault", "SIGSEGV".to_string())
+        );
+        assert_eq!(
+            describe_signal(libc::SIGKILL),
+            ("killed", "SIGKILL".to_string())
+        );
+        assert_eq!(describe_signal(libc::SIGWINCH).0, "signalled");
+
+        let mut runner = Runner::new("a".to_string(), "true".to_string());
+        let _ = runner.update(Message::ScriptRun);
+        let _ = runner.update(Message::ScriptComplete {
+            status: 1,
+            signal: Some(libc::SIGSEGV),
+            start_time: runner.start_time().unwrap(),
+            end_time: std::time::SystemTime::now(),
+        });
+        assert!(matches!(
+            runner.status,
+            Status::Completed {
+                signal: Some(libc::SIGSEGV),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn timed_out_runs_are_marked_as_such() {
+        let mut runner = Runner::new("a".to_string(), "sleep 60".to_string());
+        runner.timeout = Some(std::time::Duration::from_secs(1));
+        let _ = runner.update(Message::ScriptRun);
+        let start_time = runner.start_time().unwrap();
+        let _ = runner.update(Message::ScriptTimedOut {
+            start_time: start_time - std::time::Duration::from_secs(1),
+        });
+        assert!(matches!(
+            runner.status,
+            Status::Running {
+                timed_out: false,
+                kill_tx: Some(_),
+                ..
+            }
+        ));
+
+        let _ = runner.update(Message::ScriptTimedOut { start_time });
+        assert!(matches!(
+            runner.status,
+            Status::Running {
+                timed_out: true,
+                kill_tx: None,
+                timeout: None,
+                ..
+            }
+        ));
+        let _ = runner.update(Message::ScriptComplete {
+            status: 143,
+            signal: None,
+            start_time,
+            end_time: std::time::SystemTime::now(),
+        });
+        assert!(runner.timed_out());
+    }
+
+    #[test]
+    fn output_streams_are_forwarded_separately() {
+        use tokio::io::AsyncWriteExt;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // Much longer than one read buffer, so later reads are partial
+        let stdout_data = (0..200).map(|i| format!("out {i}\n")).collect::<String>();
+        let stderr_data = (0..50).map(|i| format!("err {i}\n")).collect::<String>();
+
+        let (stdout, stderr) = rt.block_on(async {
+            let (mut stdout_writer, stdout_reader) = tokio::io::duplex(64);
+            let (mut stderr_writer, stderr_reader) = tokio::io::duplex(64);
+            let (stdout_tx, mut stdout_rx) = mpsc::channel(1024);
+            let (stderr_tx, mut stderr_rx) = mpsc::channel(1024);
+
+            let reader = tokio::spawn(Runner::read_output(
+                "test".to_string(),
+                16,
+                encoding_rs::UTF_8,
+                stdout_reader,
+                stderr_reader,
+                stdout_tx,
+                stderr_tx,
+            ));
+
+            let stdout_bytes = stdout_data.clone().into_bytes();
+            let stderr_bytes = stderr_data.clone().into_bytes();
+            let writer = tokio::spawn(async move {
+                for (out, err) in stdout_bytes.chunks(37).zip(stderr_bytes.chunks(11)) {
+                    stdout_writer.write_all(out).await.unwrap();
+                    stderr_writer.write_all(err).await.unwrap();
+                }
+                let n = stderr_bytes.len().div_ceil(11) * 37;
+                stdout_writer.write_all(&stdout_bytes[n..]).await.unwrap();
+            });
+
+            writer.await.unwrap();
+            reader.await.unwrap();
+
+            let mut stdout = String::new();
+            while let Some(s) = stdout_rx.recv().await {
+                stdout.push_str(&s);
+            }
+            let mut stderr = String::new();
+            while let Some(s) = stderr_rx.recv().await {
+                stderr.push_str(&s);
+            }
+            (stdout, stderr)
+        });
+
+        assert_eq!(stdout, stdout_data);
+        assert_eq!(stderr, stderr_data);
+    }
+
+    /// Returns one chunk per `read` call
+    struct Chunks(std::collections::VecDeque<Vec<u8>>);
+
+    impl tokio::io::AsyncRead for Chunks {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            if let Some(chunk) = self.0.pop_front() {
+                buf.put_slice(&chunk);
+            }
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn reading_stops_once_nobody_is_listening() {
+        use tokio::io::AsyncWriteExt;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let (mut stdout_writer, stdout_reader) = tokio::io::duplex(64);
+            // Never closed, so reading would go on forever
+            let (_stderr_writer, stderr_reader) = tokio::io::duplex(64);
+            let (stdout_tx, stdout_rx) = mpsc::channel(1);
+            let (stderr_tx, _stderr_rx) = mpsc::channel(1);
+            drop(stdout_rx);
+
+            let reader = tokio::spawn(Runner::read_output(
+                "test".to_string(),
+                DEFAULT_READ_BUFFER_SIZE,
+                encoding_rs::UTF_8,
+                stdout_reader,
+                stderr_reader,
+                stdout_tx,
+                stderr_tx,
+            ));
+            stdout_writer.write_all(b"lost\n").await.unwrap();
+
+            tokio::time::timeout(std::time::Duration::from_secs(1), reader)
+                .await
+                .expect("reading should stop")
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn utf8_split_across_reads() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let text = "caf\u{e9} \u{2713} \u{1f600}!";
+        let bytes = text.as_bytes();
+        // Split inside the 4 byte emoji
+        let split = text.find('\u{1f600}').unwrap() + 2;
+
+        let stdout = rt.block_on(async {
+            let (stdout_tx, mut stdout_rx) = mpsc::channel(1024);
+            let (stderr_tx, _stderr_rx) = mpsc::channel(1024);
+
+            Runner::read_output(
+                "test".to_string(),
+                DEFAULT_READ_BUFFER_SIZE,
+                encoding_rs::UTF_8,
+                Chunks([bytes[..split].to_vec(), bytes[split..].to_vec()].into()),
+                Chunks([].into()),
+                stdout_tx,
+                stderr_tx,
+            )
+            .await;
+
+            let mut chunks = Vec::new();
+            while let Some(s) = stdout_rx.recv().await {
+                chunks.push(s);
+            }
+            chunks
+        });
+
+        assert_eq!(stdout.concat(), text);
+        assert!(
+            stdout
+                .iter()
+                .all(|s| !s.contains(char::REPLACEMENT_CHARACTER))
+        );
+    }
+
+    #[test]
+    fn utf8_decoder_replaces_invalid_bytes() {
+        let mut decoder = utf8::Decoder::default();
+        assert_eq!(decoder.decode(b"a\xffb\xe2\x9c"), "a\u{fffd}b");
+        assert_eq!(decoder.decode(b"\x93c"), "\u{2713}c");
+        assert_eq!(decoder.decode(b"\xe2"), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn legacy_encodings_are_decoded() {
+        let mut decoder = decode::Decoder::new(encoding_rs::SHIFT_JIS);
+        // "日本", split inside the first character
+        assert_eq!(decoder.decode(b"\x93"), "");
+        assert_eq!(decoder.decode(b"\xfa\x96\x7b!"), "\u{65e5}\u{672c}!");
+        assert_eq!(decoder.decode(b"\x93"), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+
+        let latin1 = encoding_rs::Encoding::for_label(b"latin1").unwrap();
+        let mut decoder = decode::Decoder::new(latin1);
+        assert_eq!(decoder.decode(b"caf\xe9"), "caf\u{e9}");
+        assert_eq!(decoder.finish(), "");
+    }
+}
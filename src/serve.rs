@@ -0,0 +1,124 @@
+//! Running every runner without the UI, as a foreground supervisor.
+
+use crate::app::{self, App};
+use crate::runner::Runner;
+
+/// Lines kept per runner when `max_log_lines` isn't set. Nothing shows
+/// them without the UI, so the whole history isn't needed.
+const MAX_LOG_LINES: usize = 1000;
+
+/// Start every runner and print their output until they've all finished,
+/// or until SIGINT or SIGTERM, when they're all shut down first.
+pub fn serve(mut runners: Vec<Runner>) -> std::process::ExitCode {
+    for runner in &mut runners {
+        runner.print_output = false;
+        runner.max_log_lines.get_or_insert(MAX_LOG_LINES);
+    }
+    let mut app = App::new(runners);
+    app.echo_lines = true;
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(async move {
+        let (message_tx, mut message_rx) = tokio::sync::mpsc::unbounded_channel();
+        // Without a window, messages are the only actions that matter
+        let spawn = |task: iced::Task<app::Message>| {
+            let Some(mut stream) = iced_runtime::task::into_stream(task) else {
+                return;
+            };
+            let message_tx = message_tx.clone();
+            tokio::spawn(async move {
+                use iced::futures::StreamExt;
+
+                while let Some(action) = stream.next().await {
+                    if let iced_runtime::Action::Output(message) = action {
+                        let _ = message_tx.send(message);
+                    }
+                }
+            });
+        };
+
+        let mut interrupts = Interrupts::new();
+
+        // Started here rather than through a task so that runners are
+        // already active when we first check whether they're all done
+        spawn(app.start_all());
+
+        let mut shutting_down = false;
+        while !app.is_idle() {
+            let shut_down = tokio::select! {
+                Some(message) = message_rx.recv() => {
+                    spawn(app.update(message));
+                    false
+                },
+                _ = interrupts.recv() => true,
+            };
+            if shut_down {
+                if shutting_down {
+                    tracing::info!("already shutting down");
+                } else {
+                    tracing::info!("shutting down");
+                    shutting_down = true;
+                    spawn(app.shut_down());
+                }
+            }
+        }
+
+        if shutting_down || app.all_succeeded() {
+            std::process::ExitCode::SUCCESS
+        } else {
+            std::process::ExitCode::FAILURE
+        }
+    })
+}
+
+/// Requests to shut down, from SIGINT and SIGTERM, or Ctrl-C and
+/// Ctrl-Break on Windows
+struct Interrupts {
+    #[cfg(unix)]
+    sigint: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    sigterm: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    ctrl_c: tokio::signal::windows::CtrlC,
+    #[cfg(windows)]
+    ctrl_break: tokio::signal::windows::CtrlBreak,
+}
+
+impl Interrupts {
+    #[cfg(unix)]
+    fn new() -> Interrupts {
+        let signal = |kind| tokio::signal::unix::signal(kind).unwrap();
+        Interrupts {
+            sigint: signal(tokio::signal::unix::SignalKind::interrupt()),
+            sigterm: signal(tokio::signal::unix::SignalKind::terminate()),
+        }
+    }
+
+    #[cfg(windows)]
+    fn new() -> Interrupts {
+        Interrupts {
+            ctrl_c: tokio::signal::windows::ctrl_c().unwrap(),
+            ctrl_break: tokio::signal::windows::ctrl_break().unwrap(),
+        }
+    }
+
+    #[cfg(unix)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.sigint.recv() => (),
+            _ = self.sigterm.recv() => (),
+        }
+    }
+
+    #[cfg(windows)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => (),
+            _ = self.ctrl_break.recv() => (),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! UI state remembered between launches.
+//!
+//! It's kept in a session file next to the config, rather than written
+//! back into the config, so the config's comments and formatting are
+//! never touched.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    /// Keyed by runner name, so state survives runners being reordered
+    #[serde(default)]
+    pub runners: BTreeMap<String, RunnerSession>,
+    pub tag_filter: Option<String>,
+    pub follow: Option<crate::app::Follow>,
+    /// Share of the window's width given to the runner panel
+    pub split: Option<f32>,
+    #[serde(default)]
+    pub runners_collapsed: bool,
+    pub window: Option<WindowGeometry>,
+}
+
+/// The window's logical size and position
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+    /// Unknown on platforms that don't report it, e.g. Wayland
+    pub position: Option<(f32, f32)>,
+}
+
+impl WindowGeometry {
+    /// Smallest size restored, so a bad save can't leave the window unusable
+    const MIN_SIZE: f32 = 200.0;
+    /// No desktop reaches this far, but minimized windows on Windows are
+    /// moved to (-32000, -32000)
+    const MAX_COORD: f32 = 16384.0;
+
+    /// This geometry, made safe to open a window with. iced doesn't list
+    /// the displays, so a position on a display that's since gone is left
+    /// for the window manager to bring back on screen.
+    pub fn restorable(self) -> Option<WindowGeometry> {
+        if !self.width.is_finite() || !self.height.is_finite() {
+            return None;
+        }
+        let in_range = |coord: f32| coord.is_finite() && coord.abs() < Self::MAX_COORD;
+        Some(WindowGeometry {
+            width: self.width.max(Self::MIN_SIZE),
+            height: self.height.max(Self::MIN_SIZE),
+            position: self.position.filter(|&(x, y)| in_range(x) && in_range(y)),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RunnerSession {
+    pub show_logs: bool,
+    pub restart: crate::runner::RestartPolicy,
+}
+
+/// The session file for the config at `config_path`, e.g.
+/// `battlestation.toml.session.json`. Configs from stdin have none.
+pub fn path(config_path: &Path) -> Option<PathBuf> {
+    if config_path == Path::new(crate::config::STDIN_PATH) {
+        return None;
+    }
+    let mut file_name = config_path.file_name()?.to_os_string();
+    file_name.push(".session.json");
+    Some(config_path.with_file_name(file_name))
+}
+
+/// The session saved at `path`, or an empty one if there isn't one yet
+pub fn load(path: &Path) -> Session {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+            tracing::warn!("ignoring session file {}: {err}", path.display());
+            Session::default()
+        }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Session::default(),
+        Err(err) => {
+            tracing::warn!("unable to read session file {}: {err}", path.display());
+            Session::default()
+        }
+    }
+}
+
+pub fn save(path: &Path, session: &Session) {
+    let contents = serde_json::to_string_pretty(session).unwrap();
+    match std::fs::write(path, contents) {
+        Ok(()) => tracing::debug!("saved session to {}", path.display()),
+        Err(err) => tracing::warn!("unable to save session to {}: {err}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sessions_round_trip() {
+        assert_eq!(path(Path::new(crate::config::STDIN_PATH)), None);
+        let dir =
+            std::env::temp_dir().join(format!("battlestation-session-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = path(&dir.join("battlestation.toml")).unwrap();
+        assert_eq!(path, dir.join("battlestation.toml.session.json"));
+        assert_eq!(load(&path), Session::default());
+
+        let session = Session {
+            runners: [(
+                "db".to_string(),
+                RunnerSession {
+                    show_logs: true,
+                    restart: crate::runner::RestartPolicy::OnFailure,
+                },
+            )]
+            .into(),
+            tag_filter: Some("backend".to_string()),
+            follow: Some(crate::app::Follow::Never),
+            split: Some(0.25),
+            runners_collapsed: true,
+            window: Some(WindowGeometry {
+                width: 800.0,
+                height: 600.0,
+                position: Some((-1920.0, 40.0)),
+            }),
+        };
+        save(&path, &session);
+        assert_eq!(load(&path), session);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn window_geometry_is_made_restorable() {
+        let geometry = |width, height, position| WindowGeometry {
+            width,
+            height,
+            position,
+        };
+        // A second display left of the first is fine
+        assert_eq!(
+            geometry(800.0, 600.0, Some((-1920.0, 40.0))).restorable(),
+            Some(geometry(800.0, 600.0, Some((-1920.0, 40.0))))
+        );
+        assert_eq!(
+            geometry(0.0, 600.0, Some((-32000.0, -32000.0))).restorable(),
+            Some(geometry(200.0, 600.0, None))
+        );
+        assert_eq!(geometry(f32::NAN, 600.0, None).restorable(), None);
+    }
+}
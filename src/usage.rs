@@ -0,0 +1,125 @@
+//! CPU and memory use of runners, counting everything their scripts start.
+
+use std::collections::HashMap;
+
+/// Time between samples. CPU use is averaged over it.
+const INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// Percent of one core, so busy process trees can pass 100
+    pub cpu: f32,
+    /// Resident memory, in bytes
+    pub memory: u64,
+}
+
+impl Usage {
+    /// Compact text for the runner list, e.g. `12% 34.5M`
+    pub fn to_short_string(self) -> String {
+        format!("{:.0}% {}", self.cpu, format_bytes(self.memory))
+    }
+}
+
+/// Samples the process table, at most once per `INTERVAL`
+pub struct Monitor {
+    system: sysinfo::System,
+    last_sample: Option<std::time::Instant>,
+}
+
+impl Monitor {
+    pub fn new() -> Monitor {
+        Monitor {
+            system: sysinfo::System::new(),
+            last_sample: None,
+        }
+    }
+
+    /// Usage of the process tree under each of `pids`, or None if it's too
+    /// soon to sample again
+    pub fn sample(&mut self, pids: &[Option<i32>]) -> Option<Vec<Option<Usage>>> {
+        let now = std::time::Instant::now();
+        if self
+            .last_sample
+            .is_some_and(|last| now.duration_since(last) < INTERVAL)
+        {
+            return None;
+        }
+        self.last_sample = Some(now);
+
+        self.system.refresh_processes_specifics(
+            sysinfo::ProcessesToUpdate::All,
+            true,
+            sysinfo::ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory(),
+        );
+
+        let mut children = HashMap::<sysinfo::Pid, Vec<sysinfo::Pid>>::new();
+        for (&pid, process) in self.system.processes() {
+            if let Some(parent) = process.parent() {
+                children.entry(parent).or_default().push(pid);
+            }
+        }
+
+        let usages = pids
+            .iter()
+            .map(|pid| {
+                let root = sysinfo::Pid::from_u32(u32::try_from((*pid)?).ok()?);
+                self.system.process(root)?;
+                let mut usage = Usage {
+                    cpu: 0.0,
+                    memory: 0,
+                };
+                let mut stack = vec![root];
+                while let Some(pid) = stack.pop() {
+                    if let Some(process) = self.system.process(pid) {
+                        usage.cpu += process.cpu_usage();
+                        usage.memory += process.memory();
+                    }
+                    stack.extend(children.get(&pid).into_iter().flatten());
+                }
+                Some(usage)
+            })
+            .collect();
+        Some(usages)
+    }
+}
+
+/// A byte count in the largest binary unit that keeps it above 1, e.g.
+/// `34.5M`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes}B")
+    } else {
+        format!("{value:.1}{}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_are_formatted_compactly() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5K");
+        assert_eq!(format_bytes(34 * 1024 * 1024 + 512 * 1024), "34.5M");
+    }
+
+    #[test]
+    fn our_own_process_is_sampled() {
+        let mut monitor = Monitor::new();
+        let pid = std::process::id() as i32;
+        let usages = monitor.sample(&[Some(pid), None]).unwrap();
+        assert!(usages[0].is_some_and(|usage| usage.memory > 0));
+        assert_eq!(usages[1], None);
+        assert_eq!(monitor.sample(&[Some(pid)]), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Watching files for changes, to restart the runners that use them.
+
+use std::path::PathBuf;
+
+use iced::futures::SinkExt;
+
+/// How long a burst of changes must settle before it's reported, so
+/// saving several files at once restarts a runner only once
+pub const DEFAULT_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Watch `paths`, and everything under any that are directories,
+/// producing runner `i`'s index each time a burst of changes settles
+pub fn subscription(
+    i: usize,
+    paths: Vec<PathBuf>,
+    debounce: std::time::Duration,
+) -> iced::Subscription<usize> {
+    iced::Subscription::run_with_id(
+        (i, paths.clone(), debounce),
+        iced::stream::channel(1, move |mut output| async move {
+            let (changes_tx, mut changes) = tokio::sync::mpsc::unbounded_channel();
+            let watcher =
+                ::notify::recommended_watcher(move |event: ::notify::Result<::notify::Event>| {
+                    match event {
+                        // Reading a file doesn't change it
+                        Ok(event) if event.kind.is_access() => (),
+                        Ok(_) => {
+                            let _ = changes_tx.send(());
+                        }
+                        Err(err) => tracing::warn!("file watch error: {err}"),
+                    }
+                });
+            // Kept until the subscription ends, as dropping it stops the watch
+            let mut watcher = match watcher {
+                Ok(watcher) => watcher,
+                Err(err) => {
+                    tracing::error!("unable to watch files: {err}");
+                    return;
+                }
+            };
+            for path in &paths {
+                use ::notify::Watcher;
+
+                if let Err(err) = watcher.watch(path, ::notify::RecursiveMode::Recursive) {
+                    tracing::warn!("unable to watch {}: {err}", path.display());
+                }
+            }
+
+            while settled(&mut changes, debounce).await {
+                if output.send(i).await.is_err() {
+                    return;
+                }
+            }
+        }),
+    )
+}
+
+/// Wait for a change, then for `debounce` to pass without another.
+/// False once no more changes can come.
+async fn settled(
+    changes: &mut tokio::sync::mpsc::UnboundedReceiver<()>,
+    debounce: std::time::Duration,
+) -> bool {
+    if changes.recv().await.is_none() {
+        return false;
+    }
+    while let Ok(Some(())) = tokio::time::timeout(debounce, changes.recv()).await {}
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bursts_of_changes_settle_once() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let debounce = std::time::Duration::from_millis(20);
+            let (changes_tx, mut changes) = tokio::sync::mpsc::unbounded_channel();
+            for _ in 0..3 {
+                changes_tx.send(()).unwrap();
+            }
+            let start = tokio::time::Instant::now();
+            assert!(settled(&mut changes, debounce).await);
+            assert!(start.elapsed() >= debounce);
+            assert!(changes.is_empty());
+
+            drop(changes_tx);
+            assert!(!settled(&mut changes, debounce).await);
+        });
+    }
+}
@@ -0,0 +1,48 @@
+//! The `run` wrapper, run as its own process. Run inside the test binary,
+//! it would take over signals like SIGINT for the rest of the tests.
+
+#![cfg(unix)]
+
+/// Run the wrapper with `args`, returning how it exited
+fn run(args: &[&str]) -> std::process::ExitStatus {
+    std::process::Command::new(env!("CARGO_BIN_EXE_battlestation"))
+        .arg("run")
+        .args(args)
+        .status()
+        .unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn stragglers_are_killed_without_a_new_session() {
+    let path = std::env::temp_dir().join(format!(
+        "battlestation-straggler-test-{}.pid",
+        std::process::id()
+    ));
+    let script = format!("sleep 30 & echo $! > {}", path.display());
+    assert!(run(&["-c", &script]).success());
+
+    let pid = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Killed, and either reaped or waiting to be
+    let stat_path = format!("/proc/{}/stat", pid.trim());
+    let start = std::time::Instant::now();
+    while let Ok(stat) = std::fs::read_to_string(&stat_path)
+        && !stat.contains(") Z ")
+    {
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(1),
+            "still running: {stat}"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn argv_is_run_without_a_shell() {
+    // A shell would split "a b" and expand $HOME
+    assert!(run(&["--", "test", "a b", "=", "a b"]).success());
+    assert!(run(&["--", "test", "$HOME", "=", "$HOME"]).success());
+    assert!(!run(&["--", "false"]).success());
+}